@@ -1,4 +1,5 @@
-use crate::{Break, WithInitActor, WithStopActor};
+use crate::trace::StopSpan;
+use crate::{trace, Break, TraceReason, WithInitActor, WithStopActor};
 use std::future::Future;
 use tokio::task::JoinHandle;
 
@@ -6,7 +7,13 @@ pub trait Actor
 where
     Self: Sized + Send + Sync + 'static,
 {
-    type ShutDown: Send + Sync + 'static + WithStopActor<Self>;
+    type ShutDown: Send + Sync + 'static + WithStopActor<Self> + TraceReason;
+
+    /// Actor name for logs, the type name by default
+    #[inline(always)]
+    fn name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
 
     fn infinite_loop(&mut self) -> impl Future<Output=Result<Break, Self::ShutDown>> + Send;
 }
@@ -18,12 +25,23 @@ where
     Break: WithStopActor<Act>,
     Act::ShutDown: WithStopActor<Act>,
 {
-    tokio::spawn(async move {
-        let mut actor: Act = init_data.init_actor().await;
-        let shutdown: Result<Break, Act::ShutDown> = actor.infinite_loop().await;
-        match shutdown {
-            Ok(shutdown) => shutdown.stop_actor(actor).await,
-            Err(shutdown) => shutdown.stop_actor(actor).await,
-        }
-    })
+    tokio::spawn(trace::actor(Act::name(), async move {
+        let actor: Act = init_data.init_actor().await;
+        run_until_stop(actor).await
+    }))
+}
+
+
+/// `infinite_loop` and the `StopActor` for its result
+pub(crate) async fn run_until_stop<Act>(mut actor: Act)
+where
+    Act: Actor,
+    Break: WithStopActor<Act>,
+    Act::ShutDown: WithStopActor<Act>,
+{
+    let shutdown: Result<Break, Act::ShutDown> = actor.infinite_loop().await;
+    match shutdown {
+        Ok(shutdown) => StopSpan::new(&shutdown).stop(shutdown.stop_actor(actor)).await,
+        Err(shutdown) => StopSpan::new(&shutdown).stop(shutdown.stop_actor(actor)).await,
+    }
 }
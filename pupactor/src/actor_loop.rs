@@ -0,0 +1,320 @@
+use crate::{Actor, ActorCommand, ActorMsg, AsyncHandle, Break, HandleBatch, PollListener};
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::convert::Infallible;
+use std::future::{poll_fn, Future};
+use std::hash::{BuildHasher, Hasher};
+use std::marker::PhantomData;
+use std::pin::pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use std::vec::Drain;
+use tokio::time::{sleep, Instant};
+
+/// Order in which the actor loop polls listeners
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SelectMode {
+    /// Start from a random listener on every iteration, like `tokio::select!`
+    #[default]
+    Random,
+    /// Declaration order, or priority order, highest first
+    Biased,
+    /// Start from the next listener on every iteration, so a busy source can not starve the others
+    RoundRobin,
+}
+
+
+/// What the actor loop does when a listener returns `None`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnClose {
+    /// Stop the actor with `Ok(Break)`
+    #[default]
+    Break,
+    /// Stop polling this listener, keep the actor running
+    Disable,
+    /// Stop polling this listener and handle `ListenerClosed`
+    Notify,
+}
+
+
+#[doc(hidden)]
+pub enum ListenerPoll {
+    /// A message waits in the listener until `LoopListeners::handle`
+    Ready,
+    Closed,
+    Pending,
+    /// Not polled, but not closed either
+    Paused,
+}
+
+
+/// Settings of `run_loop`, `on_close` has one entry per listener in polling order
+#[doc(hidden)]
+pub struct LoopConfig<'a> {
+    pub select: SelectMode,
+    pub on_close: &'a [OnClose],
+    pub idle_timeout: Option<Duration>,
+}
+
+
+/// Listeners of an actor by index, implemented by `#[derive(Pupactor)]` and by `ActorBuilder`
+///
+/// A polled message stays in its listener until `handle`, so `run_loop` does not depend on message types.
+#[doc(hidden)]
+pub trait LoopListeners<Shutdown>: Send {
+    fn poll_listener(&mut self, index: usize, cx: &mut Context<'_>) -> ListenerPoll;
+
+    fn handle(&mut self, index: usize) -> impl Future<Output=ActorCommand<Shutdown>> + Send;
+
+    /// `ListenerClosed` for a listener with `OnClose::Notify`
+    #[allow(unused_variables, clippy::manual_async_fn)]
+    fn closed(&mut self, index: usize) -> impl Future<Output=ActorCommand<Shutdown>> + Send {
+        async { ActorCommand(Ok(())) }
+    }
+}
+
+
+enum Event {
+    Ready(usize),
+    Closed(usize),
+    /// Every listener is closed or `idle_timeout` elapsed
+    Stop,
+}
+
+
+/// The actor loop shared by `#[derive(Pupactor)]` and `ActorBuilder`
+#[doc(hidden)]
+pub async fn run_loop<L, Shutdown>(listeners: &mut L, config: &LoopConfig<'_>) -> Result<Break, Shutdown>
+where
+    L: LoopListeners<Shutdown>,
+{
+    let count = config.on_close.len();
+    if count == 0 {
+        return Ok(Break);
+    }
+    // Закрытые listener'ы с Disable и Notify больше не опрашиваются
+    let mut open = vec![true; count];
+    let mut turn = 0;
+    // Таймер простоя перезапускается после обработки каждого события
+    let mut idle = pin!(config.idle_timeout.map(sleep));
+    loop {
+        let event = poll_fn(|cx| {
+            let start = match config.select {
+                SelectMode::Biased => 0,
+                SelectMode::Random => random_index(count),
+                SelectMode::RoundRobin => turn,
+            };
+            let mut waiting = false;
+            for offset in 0..count {
+                let index = (start + offset) % count;
+                if !open[index] {
+                    continue;
+                }
+                match listeners.poll_listener(index, cx) {
+                    ListenerPoll::Ready => return Poll::Ready(Event::Ready(index)),
+                    ListenerPoll::Closed => return Poll::Ready(Event::Closed(index)),
+                    // Listener на паузе не закрыт - актор ждет, даже если на паузе все
+                    ListenerPoll::Pending | ListenerPoll::Paused => waiting = true,
+                }
+            }
+            if !waiting {
+                return Poll::Ready(Event::Stop);
+            }
+            if let Some(idle) = idle.as_mut().as_pin_mut() {
+                if idle.poll(cx).is_ready() {
+                    return Poll::Ready(Event::Stop);
+                }
+            }
+            Poll::Pending
+        }).await;
+
+        let command = match event {
+            Event::Ready(index) => {
+                // Следующий опрос начинается за сработавшим listener'ом, а не за стартовым
+                turn = (index + 1) % count;
+                listeners.handle(index).await
+            }
+            Event::Closed(index) => {
+                turn = (index + 1) % count;
+                match config.on_close[index] {
+                    OnClose::Break => return Ok(Break),
+                    OnClose::Disable => {
+                        open[index] = false;
+                        ActorCommand(Ok(()))
+                    }
+                    OnClose::Notify => {
+                        open[index] = false;
+                        listeners.closed(index).await
+                    }
+                }
+            }
+            Event::Stop => return Ok(Break),
+        };
+        // Простой отсчитывается от конца обработки, долгий обработчик не останавливает актор
+        if let (Some(idle), Some(timeout)) = (idle.as_mut().as_pin_mut(), config.idle_timeout) {
+            idle.reset(Instant::now() + timeout);
+        }
+        if let Err(stop) = command.0 {
+            return stop;
+        }
+    }
+}
+
+
+/// Listener field of a `#[derive(Pupactor)]` actor, keeps polled messages until they are handled
+#[doc(hidden)]
+pub trait LoopSource {
+    type Msg;
+    type Shutdown;
+
+    /// Up to `limit` messages go to `pending`
+    fn poll_pending(&mut self, cx: &mut Context<'_>, limit: usize) -> ListenerPoll;
+
+    fn pending(&mut self) -> &mut Vec<ActorMsg<Self::Msg, Self::Shutdown>>;
+}
+
+
+/// Messages left in `pending` come first, then up to `limit` new ones
+pub(crate) fn poll_pending<Msg, Shutdown>(
+    listener: &mut impl PollListener<Msg, Shutdown>,
+    pending: &mut Vec<ActorMsg<Msg, Shutdown>>,
+    cx: &mut Context<'_>,
+    limit: usize,
+) -> ListenerPoll {
+    if !pending.is_empty() {
+        return ListenerPoll::Ready;
+    }
+    match listener.poll_next_batch(cx, pending, limit) {
+        Poll::Ready(0) => ListenerPoll::Closed,
+        Poll::Ready(_) => ListenerPoll::Ready,
+        Poll::Pending => ListenerPoll::Pending,
+    }
+}
+
+
+/// Listener polled first by `SelectMode::Random`, the same choice `tokio::select!` makes
+fn random_index(count: usize) -> usize {
+    thread_local! {
+        static STATE: Cell<u64> = Cell::new(RandomState::new().build_hasher().finish() | 1);
+    }
+    STATE.with(|state| {
+        // xorshift64, нулевое состояние исключено через `| 1`
+        let mut x = state.get();
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        state.set(x);
+        (x % count as u64) as usize
+    })
+}
+
+
+/// Messages of a `#[listener(batch = N)]` batch, drained from the reused listener buffer
+///
+/// Messages the handler leaves in the iterator are dropped with it.
+pub struct Batch<'a, Msg, Shutdown = Infallible> {
+    drain: Drain<'a, ActorMsg<Msg, Shutdown>>,
+}
+
+
+impl<Msg, Shutdown> Iterator for Batch<'_, Msg, Shutdown> {
+    type Item = Msg;
+
+    #[inline(always)]
+    fn next(&mut self) -> Option<Msg> {
+        match self.drain.next()? {
+            ActorMsg::Msg(msg) => Some(msg),
+            // `split_batch` оставляет в буфере только сообщения
+            ActorMsg::Shutdown(_) => None,
+        }
+    }
+
+    #[inline(always)]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.drain.size_hint()
+    }
+}
+
+
+impl<Msg, Shutdown> ExactSizeIterator for Batch<'_, Msg, Shutdown> {}
+
+
+/// Messages up to the first `Shutdown` and that `Shutdown`, the messages after it are dropped
+#[doc(hidden)]
+pub fn split_batch<Msg, Shutdown>(buffer: &mut Vec<ActorMsg<Msg, Shutdown>>) -> (Batch<'_, Msg, Shutdown>, Option<Shutdown>) {
+    let shutdown = buffer.iter().position(|msg| matches!(msg, ActorMsg::Shutdown(_))).and_then(|at| {
+        match buffer.drain(at..).next() {
+            Some(ActorMsg::Shutdown(shutdown)) => Some(shutdown),
+            _ => None,
+        }
+    });
+    (Batch { drain: buffer.drain(..) }, shutdown)
+}
+
+
+/// Autoref dispatch of a batch: `HandleBatch<T>` if the actor implements it,
+/// otherwise `AsyncHandle<T>` for every message
+///
+/// `(&BatchDispatch::<Self, _>::of(&batch)).dispatch(self, batch)` with both traits in scope.
+#[doc(hidden)]
+pub struct BatchDispatch<Act, T>(PhantomData<fn(Act, T)>);
+
+
+impl<Act, T> BatchDispatch<Act, T> {
+    #[inline(always)]
+    pub fn of<Shutdown>(_: &Batch<'_, T, Shutdown>) -> Self {
+        BatchDispatch(PhantomData)
+    }
+}
+
+
+#[doc(hidden)]
+pub trait ViaHandleBatch<Act: Actor, T> {
+    fn dispatch(&self, actor: &mut Act, batch: impl ExactSizeIterator<Item=T> + Send) -> impl Future<Output=ActorCommand<Act::ShutDown>> + Send;
+}
+
+
+impl<Act, T> ViaHandleBatch<Act, T> for BatchDispatch<Act, T>
+where
+    Act: HandleBatch<T>,
+    T: Send + 'static,
+{
+    #[inline(always)]
+    async fn dispatch(&self, actor: &mut Act, batch: impl ExactSizeIterator<Item=T> + Send) -> ActorCommand<Act::ShutDown> {
+        actor.handle_batch(batch).await.into()
+    }
+}
+
+
+#[doc(hidden)]
+pub trait ViaAsyncHandle<Act: Actor, T> {
+    fn dispatch(&self, actor: &mut Act, batch: impl ExactSizeIterator<Item=T> + Send) -> impl Future<Output=ActorCommand<Act::ShutDown>> + Send;
+}
+
+
+impl<Act, T> ViaAsyncHandle<Act, T> for &BatchDispatch<Act, T>
+where
+    Act: AsyncHandle<T>,
+    T: Send + 'static,
+{
+    #[inline(always)]
+    fn dispatch(&self, actor: &mut Act, batch: impl ExactSizeIterator<Item=T> + Send) -> impl Future<Output=ActorCommand<Act::ShutDown>> + Send {
+        handle_each(actor, batch)
+    }
+}
+
+
+/// `AsyncHandle<T>` for every message until a handler stops the actor
+pub(crate) async fn handle_each<Act, T>(actor: &mut Act, batch: impl Iterator<Item=T> + Send) -> ActorCommand<Act::ShutDown>
+where
+    Act: AsyncHandle<T>,
+    T: Send + 'static,
+{
+    for value in batch {
+        let command: ActorCommand<Act::ShutDown> = actor.async_handle(value).await.into();
+        if command.0.is_err() {
+            return command;
+        }
+    }
+    ActorCommand(Ok(()))
+}
@@ -1,17 +1,30 @@
+use crate::Listener;
 use std::convert::Infallible;
+use std::fmt;
 use std::future::Future;
 use std::pin::Pin;
 
 use std::task::{Context, Poll};
 use tokio::sync::mpsc::error::SendError;
-use tokio::sync::mpsc::UnboundedSender;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 use tokio::sync::oneshot;
 
 pub struct ActorRef<Msg, Shutdown = Infallible> {
     inner: UnboundedSender<ActorMsg<Msg, Shutdown>>,
 }
 
+impl<Msg, Shutdown> Clone for ActorRef<Msg, Shutdown> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+/// Listener for the receiving end of `ActorRef`
+pub type Mailbox<Msg, Shutdown = Infallible> = Listener<UnboundedReceiver<ActorMsg<Msg, Shutdown>>, Msg, Shutdown>;
+
 
+#[derive(Clone)]
 pub enum ActorMsg<Msg, Shutdown = Infallible> {
     Msg(Msg),
     Shutdown(Shutdown),
@@ -31,6 +44,13 @@ impl<Msg, Shutdown> ActorRef<Msg, Shutdown> {
         Self { inner }
     }
 
+    /// `ActorRef` with the mailbox listener for the actor
+    #[inline]
+    pub fn channel() -> (Self, Mailbox<Msg, Shutdown>) {
+        let (sender, receiver) = unbounded_channel();
+        (Self::new(sender), Listener::new(receiver))
+    }
+
     #[inline]
     pub fn try_send<M>(&self, msg: M) -> Result<(), SendError<ActorMsg<Msg, Shutdown>>>
     where
@@ -56,6 +76,13 @@ impl<Msg, Shutdown> ActorRef<Msg, Shutdown> {
         rx
     }
 
+    /// Sends the message built around a reply sender and waits for the reply
+    pub async fn ask_with<Resp>(&self, msg: impl FnOnce(oneshot::Sender<Resp>) -> Msg) -> Result<Resp, AskError> {
+        let (tx, rx) = oneshot::channel::<Resp>();
+        self.inner.send(ActorMsg::Msg(msg(tx))).map_err(|_| AskError)?;
+        rx.await.map_err(|_| AskError)
+    }
+
     pub fn ask_or_default<Resp>(&self) -> PendingRespOrDefault<Resp>
     where
         Msg: From<oneshot::Sender<Resp>>,
@@ -82,6 +109,19 @@ impl<Msg, Shutdown> ActorRef<Msg, Shutdown> {
 }
 
 
+/// The actor stopped or dropped the request without a reply
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AskError;
+
+impl fmt::Display for AskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("actor stopped before replying")
+    }
+}
+
+impl std::error::Error for AskError {}
+
+
 pub struct PendingRespOrDefault<T>(oneshot::Receiver<T>);
 
 impl<T> Future for PendingRespOrDefault<T>
@@ -0,0 +1,145 @@
+use crate::actor::run_until_stop;
+use crate::{trace, Actor, Break, WithInitActor, WithStopActor};
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::task::JoinHandle;
+
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output=T> + Send + 'a>>;
+
+/// Object safe actor: runs `infinite_loop` and the matching `StopActor`
+///
+/// Implemented for every actor that can be stopped with `Break` and its `ShutDown`,
+/// so different actor types fit in one `Vec<Box<dyn DynActor>>`.
+pub trait DynActor: Send + 'static {
+    fn name(&self) -> &'static str;
+
+    fn run(self: Box<Self>) -> BoxFuture<'static, ()>;
+}
+
+
+impl<Act> DynActor for Act
+where
+    Act: Actor,
+    Break: WithStopActor<Act>,
+    Act::ShutDown: WithStopActor<Act>,
+{
+    #[inline(always)]
+    fn name(&self) -> &'static str {
+        Act::name()
+    }
+
+    fn run(self: Box<Self>) -> BoxFuture<'static, ()> {
+        Box::pin(trace::actor(Act::name(), run_until_stop(*self)))
+    }
+}
+
+
+/// Type erased actor run, nothing happens until it is spawned or awaited
+///
+/// It is a `Future`, so a supervisor can put any mix of actors into a `JoinSet`.
+pub struct BoxedActor {
+    name: &'static str,
+    run: BoxFuture<'static, ()>,
+}
+
+
+impl BoxedActor {
+    #[inline]
+    pub fn new(actor: impl DynActor) -> Self {
+        Self::from_box(Box::new(actor))
+    }
+
+    #[inline]
+    pub fn from_box(actor: Box<dyn DynActor>) -> Self {
+        BoxedActor { name: actor.name(), run: actor.run() }
+    }
+
+    /// Same as `run_actor`, the actor is created from `init_data` once the run starts
+    pub fn init<Act>(init_data: impl WithInitActor<Act>) -> Self
+    where
+        Act: Actor,
+        Break: WithStopActor<Act>,
+        Act::ShutDown: WithStopActor<Act>,
+    {
+        BoxedActor {
+            name: Act::name(),
+            run: Box::pin(trace::actor(Act::name(), async move {
+                let actor: Act = init_data.init_actor().await;
+                run_until_stop(actor).await
+            })),
+        }
+    }
+
+    #[inline(always)]
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    #[inline]
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run)
+    }
+}
+
+
+impl Future for BoxedActor {
+    type Output = ();
+
+    #[inline(always)]
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.run.as_mut().poll(cx)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pupactor, StopActor};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::task::JoinSet;
+
+    #[derive(Pupactor)]
+    struct First {
+        stopped: Arc<AtomicUsize>,
+    }
+
+    impl StopActor<Break> for First {
+        async fn stop_actor(self, _: Break) {
+            self.stopped.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(Pupactor)]
+    #[actor(name = "second")]
+    struct Second {
+        stopped: Arc<AtomicUsize>,
+    }
+
+    impl StopActor<Break> for Second {
+        async fn stop_actor(self, _: Break) {
+            self.stopped.fetch_add(10, Ordering::SeqCst);
+        }
+    }
+
+    #[tokio::test]
+    async fn different_actors_run_together() {
+        let stopped = Arc::new(AtomicUsize::new(0));
+        let actors: Vec<Box<dyn DynActor>> = vec![
+            Box::new(First { stopped: stopped.clone() }),
+            Box::new(Second { stopped: stopped.clone() }),
+        ];
+        let actors = actors.into_iter().map(BoxedActor::from_box).collect::<Vec<_>>();
+        assert_eq!(actors[1].name(), "second");
+
+        let mut join_set = JoinSet::new();
+        for actor in actors {
+            join_set.spawn(actor);
+        }
+        while join_set.join_next().await.is_some() {}
+
+        assert_eq!(stopped.load(Ordering::SeqCst), 11);
+    }
+}
@@ -0,0 +1,572 @@
+use crate::actor_loop::{poll_pending, run_loop, split_batch, ListenerPoll, LoopConfig, LoopListeners};
+use crate::trace::{MessageSpan, StopSpan};
+use crate::{trace, ActorCommand, ActorMsg, Batch, BoxFuture, Break, ListenerClosed, OnClose, PollListener, SelectMode, TraceReason};
+use std::cmp::Reverse;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Context, Waker};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+
+/// Actor without macros: a state, listeners with their handlers and an optional `on_stop`
+///
+/// Runs the same loop as `#[derive(Pupactor)]`: select modes, priorities, batches, pausing,
+/// `ActorCommand` results of handlers, `ActorMsg::Shutdown`, close handling and `idle_timeout`.
+/// Listeners are polled in random order by default, like the derive, see `select_mode`.
+/// Handlers borrow the state, so their futures are boxed:
+/// `.listener(interval, |state, tick: Instant| Box::pin(async move { .. }))`.
+pub struct ActorBuilder<S, Shutdown = Infallible> {
+    name: &'static str,
+    state: S,
+    listeners: Vec<Slot<S, Shutdown>>,
+    select: SelectMode,
+    idle_timeout: Option<Duration>,
+    on_closed: Option<Box<dyn ClosedHandler<S, Shutdown>>>,
+    on_stop: Option<OnStop<S, Shutdown>>,
+}
+
+type OnStop<S, Shutdown> = Box<dyn FnOnce(S, Result<Break, Shutdown>) -> BoxFuture<'static, ()> + Send>;
+
+struct Slot<S, Shutdown> {
+    listener: Box<dyn BuiltListener<S, Shutdown>>,
+    name: &'static str,
+    on_close: OnClose,
+    priority: Option<i64>,
+    pause: Option<PauseHandle>,
+}
+
+
+/// Settings of one `ActorBuilder` listener, the counterpart of `#[listener(...)]`
+#[derive(Clone, Default)]
+pub struct ListenerOptions {
+    name: Option<&'static str>,
+    on_close: OnClose,
+    priority: Option<i64>,
+    pause: Option<PauseHandle>,
+}
+
+
+impl ListenerOptions {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `ListenerClosed::listener` for `OnClose::Notify`, the listener type name by default
+    #[inline]
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
+    #[inline]
+    pub fn on_close(mut self, on_close: OnClose) -> Self {
+        self.on_close = on_close;
+        self
+    }
+
+    /// Higher is polled first, a priority on any listener makes polling biased
+    #[inline]
+    pub fn priority(mut self, priority: i64) -> Self {
+        self.priority = Some(priority);
+        self
+    }
+
+    /// The listener is not polled while `pause` is paused
+    #[inline]
+    pub fn pause(mut self, pause: &PauseHandle) -> Self {
+        self.pause = Some(pause.clone());
+        self
+    }
+}
+
+
+/// Pauses an `ActorBuilder` listener, the counterpart of `Listener::pause`
+///
+/// Keep a clone in the state to pause from handlers, `resume` from another task wakes the actor.
+/// A paused listener is not closed, the actor keeps running even if every listener is paused.
+#[derive(Clone, Default)]
+pub struct PauseHandle {
+    inner: Arc<PauseState>,
+}
+
+#[derive(Default)]
+struct PauseState {
+    paused: AtomicBool,
+    waker: Mutex<Option<Waker>>,
+}
+
+
+impl PauseHandle {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[inline]
+    pub fn pause(&self) {
+        self.inner.paused.store(true, Ordering::SeqCst);
+    }
+
+    pub fn resume(&self) {
+        self.inner.paused.store(false, Ordering::SeqCst);
+        if let Some(waker) = self.inner.waker.lock().unwrap_or_else(PoisonError::into_inner).take() {
+            waker.wake();
+        }
+    }
+
+    #[inline]
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::SeqCst)
+    }
+
+    /// While paused the actor loop waits for `resume`
+    fn poll_paused(&self, cx: &mut Context<'_>) -> bool {
+        if !self.is_paused() {
+            return false;
+        }
+        *self.inner.waker.lock().unwrap_or_else(PoisonError::into_inner) = Some(cx.waker().clone());
+        // resume мог пройти между проверкой и регистрацией waker'а
+        self.is_paused()
+    }
+}
+
+
+impl<S> ActorBuilder<S>
+where
+    S: Send + 'static,
+{
+    /// Actor that stops only with `Break`
+    #[inline]
+    pub fn new(state: S) -> Self {
+        Self::with_shutdown(state)
+    }
+}
+
+
+impl<S, Shutdown> ActorBuilder<S, Shutdown>
+where
+    S: Send + 'static,
+    Shutdown: TraceReason + Send + 'static,
+{
+    /// Actor that can be stopped with `Kill` or `ActorMsg::Shutdown` of `Shutdown`
+    pub fn with_shutdown(state: S) -> Self {
+        ActorBuilder {
+            name: std::any::type_name::<S>(),
+            state,
+            listeners: Vec::new(),
+            select: SelectMode::default(),
+            idle_timeout: None,
+            on_closed: None,
+            on_stop: None,
+        }
+    }
+
+    /// Actor name for logs, the state type name by default
+    #[inline]
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = name;
+        self
+    }
+
+    /// Stops the actor when the listener closes
+    #[inline]
+    pub fn listener<L, Msg, H, R>(self, listener: L, handler: H) -> Self
+    where
+        L: PollListener<Msg, Shutdown> + Send + 'static,
+        Msg: Send + 'static,
+        H: for<'a> FnMut(&'a mut S, Msg) -> BoxFuture<'a, R> + Send + 'static,
+        R: Into<ActorCommand<Shutdown>> + 'static,
+    {
+        self.listener_with(listener, ListenerOptions::new(), handler)
+    }
+
+    #[inline]
+    pub fn listener_on_close<L, Msg, H, R>(self, listener: L, on_close: OnClose, handler: H) -> Self
+    where
+        L: PollListener<Msg, Shutdown> + Send + 'static,
+        Msg: Send + 'static,
+        H: for<'a> FnMut(&'a mut S, Msg) -> BoxFuture<'a, R> + Send + 'static,
+        R: Into<ActorCommand<Shutdown>> + 'static,
+    {
+        self.listener_with(listener, ListenerOptions::new().on_close(on_close), handler)
+    }
+
+    pub fn listener_with<L, Msg, H, R>(self, listener: L, options: ListenerOptions, handler: H) -> Self
+    where
+        L: PollListener<Msg, Shutdown> + Send + 'static,
+        Msg: Send + 'static,
+        H: for<'a> FnMut(&'a mut S, Msg) -> BoxFuture<'a, R> + Send + 'static,
+        R: Into<ActorCommand<Shutdown>> + 'static,
+    {
+        let listener = HandledListener { listener, handler, pending: Vec::new() };
+        self.push::<L>(Box::new(listener), options)
+    }
+
+    /// Up to `limit` queued messages go to the handler at once, see `#[listener(batch = N)]`
+    ///
+    /// Panics if `limit` is 0.
+    pub fn batch_listener<L, Msg, H, R>(self, listener: L, limit: usize, options: ListenerOptions, handler: H) -> Self
+    where
+        L: PollListener<Msg, Shutdown> + Send + 'static,
+        Msg: Send + 'static,
+        H: for<'a> FnMut(&'a mut S, Batch<'a, Msg, Shutdown>) -> BoxFuture<'a, R> + Send + 'static,
+        R: Into<ActorCommand<Shutdown>> + 'static,
+    {
+        assert!(limit > 0, "batch size must be greater than 0");
+        let listener = BatchListener { listener, handler, limit, pending: Vec::with_capacity(limit) };
+        self.push::<L>(Box::new(listener), options)
+    }
+
+    fn push<L>(mut self, listener: Box<dyn BuiltListener<S, Shutdown>>, options: ListenerOptions) -> Self {
+        let ListenerOptions { name, on_close, priority, pause } = options;
+        let name = name.unwrap_or(std::any::type_name::<L>());
+        self.listeners.push(Slot { listener, name, on_close, priority, pause });
+        self
+    }
+
+    /// Handles `ListenerClosed` of listeners with `OnClose::Notify`
+    pub fn on_listener_closed<H, R>(mut self, handler: H) -> Self
+    where
+        H: for<'a> FnMut(&'a mut S, ListenerClosed) -> BoxFuture<'a, R> + Send + 'static,
+        R: Into<ActorCommand<Shutdown>> + 'static,
+    {
+        self.on_closed = Some(Box::new(handler));
+        self
+    }
+
+    /// Order in which listeners are polled, `SelectMode::Random` by default,
+    /// ignored once a listener has a priority
+    #[inline]
+    pub fn select_mode(mut self, select: SelectMode) -> Self {
+        self.select = select;
+        self
+    }
+
+    /// Poll listeners in the order they were added
+    #[inline]
+    pub fn biased(self) -> Self {
+        self.select_mode(SelectMode::Biased)
+    }
+
+    /// Stops the actor with `Break` when no listener fires in time
+    #[inline]
+    pub fn idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Called with the state and the stop reason, the `StopActor` of the builder
+    pub fn on_stop<F, Fut>(mut self, on_stop: F) -> Self
+    where
+        F: FnOnce(S, Result<Break, Shutdown>) -> Fut + Send + 'static,
+        Fut: Future<Output=()> + Send + 'static,
+    {
+        self.on_stop = Some(Box::new(move |state, stop| Box::pin(on_stop(state, stop))));
+        self
+    }
+
+    pub async fn run(self) {
+        trace::actor(self.name, self.run_until_stop()).await
+    }
+
+    async fn run_until_stop(mut self) {
+        let select = if self.listeners.iter().any(|slot| slot.priority.is_some()) {
+            // Стабильная сортировка: при равном приоритете порядок добавления
+            self.listeners.sort_by_key(|slot| Reverse(slot.priority.unwrap_or(0)));
+            SelectMode::Biased
+        } else {
+            self.select
+        };
+        let on_close = self.listeners.iter().map(|slot| slot.on_close).collect::<Vec<_>>();
+        let config = LoopConfig { select, on_close: &on_close, idle_timeout: self.idle_timeout };
+        let stop = run_loop(&mut self, &config).await;
+        if let Some(on_stop) = self.on_stop {
+            let span = match &stop {
+                Ok(stop) => StopSpan::new(stop),
+                Err(stop) => StopSpan::new(stop),
+            };
+            span.stop(on_stop(self.state, stop)).await;
+        }
+    }
+
+    #[inline]
+    pub fn spawn(self) -> JoinHandle<()> {
+        tokio::spawn(self.run())
+    }
+}
+
+
+impl<S, Shutdown> LoopListeners<Shutdown> for ActorBuilder<S, Shutdown>
+where
+    S: Send + 'static,
+    Shutdown: Send + 'static,
+{
+    fn poll_listener(&mut self, index: usize, cx: &mut Context<'_>) -> ListenerPoll {
+        let slot = &mut self.listeners[index];
+        if slot.pause.as_ref().is_some_and(|pause| pause.poll_paused(cx)) {
+            return ListenerPoll::Paused;
+        }
+        slot.listener.poll(cx)
+    }
+
+    fn handle(&mut self, index: usize) -> impl Future<Output=ActorCommand<Shutdown>> + Send {
+        self.listeners[index].listener.handle(&mut self.state)
+    }
+
+    async fn closed(&mut self, index: usize) -> ActorCommand<Shutdown> {
+        let closed = ListenerClosed { listener: self.listeners[index].name };
+        match self.on_closed.as_mut() {
+            Some(on_closed) => on_closed.handle(&mut self.state, closed).await,
+            None => ActorCommand(Ok(())),
+        }
+    }
+}
+
+
+/// Listener with its handler, erased over the message type
+///
+/// A polled message waits in the listener until `handle`.
+trait BuiltListener<S, Shutdown>: Send {
+    fn poll(&mut self, cx: &mut Context<'_>) -> ListenerPoll;
+
+    fn handle<'a>(&'a mut self, state: &'a mut S) -> BoxFuture<'a, ActorCommand<Shutdown>>;
+}
+
+
+struct HandledListener<L, Msg, Shutdown, H> {
+    listener: L,
+    handler: H,
+    pending: Vec<ActorMsg<Msg, Shutdown>>,
+}
+
+
+impl<S, Shutdown, L, Msg, H, R> BuiltListener<S, Shutdown> for HandledListener<L, Msg, Shutdown, H>
+where
+    L: PollListener<Msg, Shutdown> + Send,
+    Msg: Send,
+    Shutdown: Send + 'static,
+    H: for<'a> FnMut(&'a mut S, Msg) -> BoxFuture<'a, R> + Send,
+    R: Into<ActorCommand<Shutdown>> + 'static,
+{
+    fn poll(&mut self, cx: &mut Context<'_>) -> ListenerPoll {
+        poll_pending(&mut self.listener, &mut self.pending, cx, 1)
+    }
+
+    fn handle<'a>(&'a mut self, state: &'a mut S) -> BoxFuture<'a, ActorCommand<Shutdown>> {
+        match self.pending.pop() {
+            Some(ActorMsg::Msg(msg)) => {
+                let span = MessageSpan::new(&msg);
+                let handle = (self.handler)(state, msg);
+                Box::pin(span.handle(async move { handle.await.into() }))
+            }
+            Some(ActorMsg::Shutdown(shutdown)) => Box::pin(async { ActorCommand(Err(Err(shutdown))) }),
+            None => Box::pin(async { ActorCommand(Ok(())) }),
+        }
+    }
+}
+
+
+struct BatchListener<L, Msg, Shutdown, H> {
+    listener: L,
+    handler: H,
+    limit: usize,
+    /// Reused for every batch
+    pending: Vec<ActorMsg<Msg, Shutdown>>,
+}
+
+
+impl<S, Shutdown, L, Msg, H, R> BuiltListener<S, Shutdown> for BatchListener<L, Msg, Shutdown, H>
+where
+    S: Send,
+    L: PollListener<Msg, Shutdown> + Send,
+    Msg: Send,
+    Shutdown: Send + 'static,
+    H: for<'a> FnMut(&'a mut S, Batch<'a, Msg, Shutdown>) -> BoxFuture<'a, R> + Send,
+    R: Into<ActorCommand<Shutdown>> + 'static,
+{
+    fn poll(&mut self, cx: &mut Context<'_>) -> ListenerPoll {
+        poll_pending(&mut self.listener, &mut self.pending, cx, self.limit)
+    }
+
+    fn handle<'a>(&'a mut self, state: &'a mut S) -> BoxFuture<'a, ActorCommand<Shutdown>> {
+        Box::pin(async move {
+            let (batch, shutdown) = split_batch(&mut self.pending);
+            let command = if batch.len() > 0 {
+                let span = MessageSpan::batch(&batch);
+                let handle = (self.handler)(state, batch);
+                span.handle(async move { handle.await.into() }).await
+            } else {
+                ActorCommand(Ok(()))
+            };
+            match shutdown {
+                Some(shutdown) if command.0.is_ok() => ActorCommand(Err(Err(shutdown))),
+                _ => command,
+            }
+        })
+    }
+}
+
+
+trait ClosedHandler<S, Shutdown>: Send {
+    fn handle<'a>(&'a mut self, state: &'a mut S, closed: ListenerClosed) -> BoxFuture<'a, ActorCommand<Shutdown>>;
+}
+
+
+impl<S, Shutdown, H, R> ClosedHandler<S, Shutdown> for H
+where
+    H: for<'a> FnMut(&'a mut S, ListenerClosed) -> BoxFuture<'a, R> + Send,
+    R: Into<ActorCommand<Shutdown>> + 'static,
+{
+    fn handle<'a>(&'a mut self, state: &'a mut S, closed: ListenerClosed) -> BoxFuture<'a, ActorCommand<Shutdown>> {
+        let handle = self(state, closed);
+        Box::pin(async move { handle.await.into() })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ActorRef, Kill};
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn builder_handles_until_break() {
+        let (actor_ref, mailbox) = ActorRef::<u32>::channel();
+        let (stopped, stopped_receiver) = oneshot::channel();
+
+        ActorBuilder::new(0u32)
+            .listener(mailbox, |sum, value: u32| Box::pin(async move {
+                *sum += value;
+                (value == 0).then_some(Break)
+            }))
+            // Closed right away, the actor keeps running
+            .listener_on_close(tokio::sync::mpsc::unbounded_channel::<ActorMsg<()>>().1, OnClose::Disable, |_, ()| Box::pin(async {}))
+            .on_stop(move |sum, stop| async move {
+                let _ = stopped.send((sum, stop.is_ok()));
+            })
+            .spawn();
+
+        actor_ref.send(2u32);
+        actor_ref.send(3u32);
+        actor_ref.send(0u32);
+        assert_eq!(stopped_receiver.await, Ok((5, true)));
+    }
+
+    #[tokio::test]
+    async fn builder_kill_reaches_on_stop() {
+        let (actor_ref, mailbox) = ActorRef::<u32, String>::channel();
+        let (stopped, stopped_receiver) = oneshot::channel();
+
+        ActorBuilder::with_shutdown(())
+            .listener(mailbox, |_, value: u32| Box::pin(async move { Kill(format!("killed by {}", value)) }))
+            .on_stop(move |_, stop| async move {
+                let _ = stopped.send(stop.err());
+            })
+            .spawn();
+
+        actor_ref.send(7u32);
+        assert_eq!(stopped_receiver.await, Ok(Some("killed by 7".to_string())));
+    }
+
+    #[tokio::test]
+    async fn builder_notifies_closed_listener() {
+        let (stopped, stopped_receiver) = oneshot::channel();
+
+        ActorBuilder::new(None)
+            .listener_with(
+                tokio::sync::mpsc::unbounded_channel::<ActorMsg<()>>().1,
+                ListenerOptions::new().name("events").on_close(OnClose::Notify),
+                |_, ()| Box::pin(async {}),
+            )
+            .on_listener_closed(|closed, event: ListenerClosed| Box::pin(async move {
+                *closed = Some(event.listener);
+                Break
+            }))
+            .on_stop(move |closed, _| async move {
+                let _ = stopped.send(closed);
+            })
+            .spawn();
+
+        assert_eq!(stopped_receiver.await, Ok(Some("events")));
+    }
+
+    #[tokio::test]
+    async fn builder_round_robin_interleaves_listeners() {
+        let (first_ref, first) = ActorRef::<u32>::channel();
+        let (second_ref, second) = ActorRef::<u32>::channel();
+        for value in 1..=2u32 {
+            first_ref.send(value);
+            second_ref.send(value * 10);
+        }
+        let (stopped, stopped_receiver) = oneshot::channel();
+
+        ActorBuilder::new(Vec::new())
+            .listener(first, |received: &mut Vec<u32>, value: u32| Box::pin(async move {
+                received.push(value);
+                (received.len() == 4).then_some(Break)
+            }))
+            .listener(second, |received, value: u32| Box::pin(async move {
+                received.push(value);
+                (received.len() == 4).then_some(Break)
+            }))
+            .select_mode(SelectMode::RoundRobin)
+            .on_stop(move |received, _| async move {
+                let _ = stopped.send(received);
+            })
+            .spawn();
+
+        assert_eq!(stopped_receiver.await, Ok(vec![1, 10, 2, 20]));
+    }
+
+    #[tokio::test]
+    async fn builder_batches_by_priority() {
+        let (normal_ref, normal) = ActorRef::<u32>::channel();
+        let (urgent_ref, urgent) = ActorRef::<u32>::channel();
+        for value in 1..=3u32 {
+            normal_ref.send(value);
+            urgent_ref.send(value * 10);
+        }
+        drop(urgent_ref);
+        let (stopped, stopped_receiver) = oneshot::channel();
+
+        ActorBuilder::new(Vec::new())
+            .batch_listener(normal, 2, ListenerOptions::new(), |batches: &mut Vec<Vec<u32>>, batch: Batch<u32>| Box::pin(async move {
+                batches.push(batch.collect());
+                (batches.len() == 3).then_some(Break)
+            }))
+            .batch_listener(urgent, 8, ListenerOptions::new().priority(1).on_close(OnClose::Disable), |batches, batch| Box::pin(async move {
+                batches.push(batch.collect());
+            }))
+            .on_stop(move |batches, _| async move {
+                let _ = stopped.send(batches);
+            })
+            .spawn();
+
+        assert_eq!(stopped_receiver.await, Ok(vec![vec![10, 20, 30], vec![1, 2], vec![3]]));
+    }
+
+    #[tokio::test]
+    async fn builder_resumes_paused_listener_from_another_task() {
+        let (actor_ref, mailbox) = ActorRef::<u32>::channel();
+        let pause = PauseHandle::new();
+        pause.pause();
+        let (seen, mut seen_receiver) = tokio::sync::mpsc::unbounded_channel();
+
+        ActorBuilder::new(seen)
+            .listener_with(mailbox, ListenerOptions::new().pause(&pause), |seen, value: u32| Box::pin(async move {
+                let _ = seen.send(value);
+            }))
+            .spawn();
+
+        actor_ref.send(1u32);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(seen_receiver.try_recv().is_err());
+
+        pause.resume();
+        assert_eq!(seen_receiver.recv().await, Some(1));
+    }
+}
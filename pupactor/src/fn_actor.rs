@@ -0,0 +1,151 @@
+use crate::trace::MessageSpan;
+use crate::{trace, ActorCommand, ActorMsg, ActorRef, BoxFuture, Break};
+use std::future::Future;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::task::JoinHandle;
+
+/// Spawns an actor from a state and a handler, messages are handled one by one
+///
+/// The handler borrows the state, so its future is boxed:
+/// `spawn_fn_actor("counter", 0, |count, msg: u32| Box::pin(async move { *count += msg; }))`.
+/// The actor stops on `Break`, `Kill` or `ActorMsg::Shutdown`, and with `Break` once every `ActorRef`
+/// is dropped. The `JoinHandle` resolves to that outcome, same as the `StopActor` argument of `run_actor`.
+/// `name` is the actor name for logs, with the `tracing` feature the task runs in an `actor` span with it.
+pub fn spawn_fn_actor<S, Msg, Shutdown, F, R>(name: &'static str, mut state: S, mut handler: F) -> (ActorRef<Msg, Shutdown>, JoinHandle<Result<Break, Shutdown>>)
+where
+    S: Send + 'static,
+    Msg: Send + 'static,
+    Shutdown: Send + 'static,
+    F: for<'a> FnMut(&'a mut S, Msg) -> BoxFuture<'a, R> + Send + 'static,
+    R: Into<ActorCommand<Shutdown>>,
+{
+    let (sender, mut receiver) = unbounded_channel::<ActorMsg<Msg, Shutdown>>();
+    let handle = tokio::spawn(trace::actor(name, async move {
+        loop {
+            let msg = match recv(&mut receiver).await {
+                Ok(msg) => msg,
+                Err(stop) => return stop,
+            };
+            let command = MessageSpan::new(&msg).handle(async { handler(&mut state, msg).await.into() }).await;
+            if let Err(stop) = command.0 {
+                return stop;
+            }
+        }
+    }));
+    (ActorRef::new(sender), handle)
+}
+
+
+/// Same as `spawn_fn_actor` without a state: `spawn_stateless("printer", |msg: u32| async move { .. })`
+pub fn spawn_stateless<Msg, Shutdown, F, Fut>(name: &'static str, mut handler: F) -> (ActorRef<Msg, Shutdown>, JoinHandle<Result<Break, Shutdown>>)
+where
+    Msg: Send + 'static,
+    Shutdown: Send + 'static,
+    F: FnMut(Msg) -> Fut + Send + 'static,
+    Fut: Future + Send,
+    Fut::Output: Into<ActorCommand<Shutdown>>,
+{
+    let (sender, mut receiver) = unbounded_channel::<ActorMsg<Msg, Shutdown>>();
+    let handle = tokio::spawn(trace::actor(name, async move {
+        loop {
+            let msg = match recv(&mut receiver).await {
+                Ok(msg) => msg,
+                Err(stop) => return stop,
+            };
+            let command = MessageSpan::new(&msg).handle(async { handler(msg).await.into() }).await;
+            if let Err(stop) = command.0 {
+                return stop;
+            }
+        }
+    }));
+    (ActorRef::new(sender), handle)
+}
+
+
+/// Next message, or the outcome once the actor has to stop
+async fn recv<Msg, Shutdown>(receiver: &mut UnboundedReceiver<ActorMsg<Msg, Shutdown>>) -> Result<Msg, Result<Break, Shutdown>> {
+    match receiver.recv().await {
+        Some(ActorMsg::Msg(msg)) => Ok(msg),
+        Some(ActorMsg::Shutdown(shutdown)) => Err(Err(shutdown)),
+        // Все ActorRef удалены
+        None => Err(Ok(Break)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Break, Kill};
+    use tokio::sync::oneshot;
+
+    enum CounterMsg {
+        Add(u32),
+        Get(oneshot::Sender<u32>),
+        Stop,
+    }
+
+    impl From<oneshot::Sender<u32>> for CounterMsg {
+        fn from(reply: oneshot::Sender<u32>) -> Self {
+            CounterMsg::Get(reply)
+        }
+    }
+
+    #[tokio::test]
+    async fn fn_actor_keeps_state_until_break() {
+        let (counter, handle): (ActorRef<CounterMsg>, _) = spawn_fn_actor("counter", 0, |count, msg| Box::pin(async move {
+            match msg {
+                CounterMsg::Add(value) => *count += value,
+                CounterMsg::Get(reply) => {
+                    let _ = reply.send(*count);
+                }
+                CounterMsg::Stop => return Some(Break),
+            }
+            None
+        }));
+
+        counter.send(CounterMsg::Add(2));
+        counter.send(CounterMsg::Add(3));
+        assert_eq!(counter.ask::<u32>().await, Ok(5));
+        counter.send(CounterMsg::Stop);
+        assert!(counter.ask::<u32>().await.is_err());
+        assert!(matches!(handle.await, Ok(Ok(Break))));
+    }
+
+    #[tokio::test]
+    async fn stateless_actor_stops_on_kill() {
+        let (seen, mut seen_receiver) = unbounded_channel();
+        let (actor, handle): (ActorRef<u32, u32>, _) = spawn_stateless("recorder", move |msg: u32| {
+            let seen = seen.clone();
+            async move {
+                let _ = seen.send(msg);
+                (msg == 2).then_some(Kill(msg))
+            }
+        });
+
+        for msg in 1u32..=3 {
+            actor.send(msg);
+        }
+        assert_eq!(seen_receiver.recv().await, Some(1));
+        assert_eq!(seen_receiver.recv().await, Some(2));
+        assert_eq!(seen_receiver.recv().await, None);
+        assert!(matches!(handle.await, Ok(Err(2))));
+    }
+
+    #[tokio::test]
+    async fn shutdown_message_is_returned() {
+        let (counter, handle): (ActorRef<CounterMsg, &str>, _) = spawn_stateless("counter", |_: CounterMsg| async {});
+
+        counter.send(CounterMsg::Add(1));
+        counter.shutdown::<u32>("deploy");
+        assert!(matches!(handle.await, Ok(Err("deploy"))));
+    }
+
+    #[tokio::test]
+    async fn dropped_refs_break() {
+        let (actor, handle): (ActorRef<u32>, _) = spawn_fn_actor("noop", (), |_, _: u32| Box::pin(async {}));
+
+        drop(actor);
+        assert!(matches!(handle.await, Ok(Ok(Break))));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::actor_loop::handle_each;
 use crate::{Actor, ActorCommand};
 use std::future::Future;
 pub trait AsyncHandle<T>
@@ -21,6 +22,7 @@ where
     T: Send + 'static,
 {
     #[inline(always)]
+    #[allow(clippy::manual_async_fn)]
     fn async_handle(&mut self, value: T) -> impl Future<Output=impl Into<ActorCommand<Self::ShutDown>>> + Send {
         async {
             self.handle(value)
@@ -28,6 +30,26 @@ where
     }
 }
 
+/// Handles up to `N` queued messages at once, see `#[listener(batch = N)]`
+///
+/// Optional: for an actor without this impl the generated loop calls `AsyncHandle<T>`
+/// for every message of the batch.
+///
+/// The batch is an iterator and not a `Vec<T>`: it is drained from a buffer the listener keeps
+/// between batches, so a busy listener does not allocate a `Vec` per batch.
+/// Call `batch.collect::<Vec<_>>()` where a `Vec` is needed. Messages left in the iterator are dropped.
+pub trait HandleBatch<T>
+where
+    Self: AsyncHandle<T>,
+    T: Send + 'static,
+{
+    /// Defaults to `AsyncHandle<T>` for every message, the rest of the batch
+    /// is dropped once a handler stops the actor
+    fn handle_batch(&mut self, batch: impl ExactSizeIterator<Item=T> + Send) -> impl Future<Output=impl Into<ActorCommand<Self::ShutDown>>> + Send {
+        handle_each(self, batch)
+    }
+}
+
 pub trait WithHandle<Act>
 where
     Self: Send + 'static,
@@ -20,6 +20,7 @@ where
 //     }
 // }
 
+#[allow(clippy::empty_line_after_doc_comments)]
 pub trait WithInitActor<Act>
 where
     Act: Actor + Send + Sync + 'static,
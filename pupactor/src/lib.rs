@@ -1,4 +1,8 @@
 pub use actor::*;
+pub use actor_loop::{Batch, OnClose, SelectMode};
+pub use boxed_actor::*;
+pub use builder::*;
+pub use fn_actor::*;
 pub use actor_command::*;
 pub use actor_ref::*;
 pub use handle::*;
@@ -9,10 +13,26 @@ pub use stop_actor::*;
 mod stop_actor;
 mod init_actor;
 mod actor;
+mod actor_loop;
+mod boxed_actor;
+mod builder;
+mod fn_actor;
 mod actor_ref;
 mod listener;
 mod actor_command;
 mod handle;
+mod trace;
 
 // macros
-pub use pupactor_macro::{ActorMsgHandle, ActorShutdown, Pupactor};
+pub use pupactor_macro::{handlers, ActorMsgHandle, ActorShutdown, Pupactor};
+
+// generated code refers to `::pupactor::...`, also from inside this crate
+extern crate self as pupactor;
+
+/// Dependencies used by the macros, not a public API
+#[doc(hidden)]
+pub mod __private {
+    pub use tokio;
+    pub use crate::actor_loop::{run_loop, split_batch, BatchDispatch, ListenerPoll, LoopConfig, LoopListeners, LoopSource, ViaAsyncHandle, ViaHandleBatch};
+    pub use crate::trace::MessageSpan;
+}
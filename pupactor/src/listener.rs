@@ -1,73 +1,218 @@
+use crate::actor_loop::{poll_pending, ListenerPoll, LoopSource};
 use crate::ActorMsg;
 use std::convert::Infallible;
-use std::future::Future;
-use std::marker::PhantomData;
+use std::future::{poll_fn, Future};
 use std::ops::{Deref, DerefMut};
-use tokio::sync::mpsc::UnboundedReceiver;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc::{Receiver, UnboundedReceiver};
 use tokio::time::{Instant, Interval};
 
+pub use adapter::*;
+pub use channel::*;
+pub use framed::*;
+pub use set::*;
+#[cfg(unix)]
+pub use signal::*;
+pub use stream::*;
+
+mod adapter;
+mod channel;
+mod framed;
+mod net;
+mod set;
+#[cfg(unix)]
+mod signal;
+mod stream;
+
+/// Future based listener
+///
+/// The future is dropped whenever another listener wins, so it has to be cancel safe.
+/// Wrap it into `FutureListener` to use it as `Listener`.
 pub trait ActorListener<Msg, Shutdown = Infallible> {
-    fn next_msg(&mut self) -> impl Future<Output=Option<ActorMsg<Msg, Shutdown>>>;
+    fn next_msg(&mut self) -> impl Future<Output=Option<ActorMsg<Msg, Shutdown>>> + Send;
+}
+
+/// Poll based listener, polled directly by the generated actor loop
+///
+/// `Poll::Ready(None)` closes the listener.
+pub trait PollListener<Msg, Shutdown = Infallible> {
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>>;
+
+    /// Appends up to `limit` ready messages to `batch`, `Poll::Ready(0)` closes the listener
+    ///
+    /// Defaults to a single `poll_next_msg`, channels drain their queue at once.
+    #[inline(always)]
+    fn poll_next_batch(&mut self, cx: &mut Context<'_>, batch: &mut Vec<ActorMsg<Msg, Shutdown>>, limit: usize) -> Poll<usize> {
+        let _ = limit;
+        self.poll_next_msg(cx).map(|msg| match msg {
+            Some(msg) => {
+                batch.push(msg);
+                1
+            }
+            None => 0,
+        })
+    }
 }
 
-pub struct Listener<T, Msg, Shutdown = Infallible>(T, PhantomData<(Msg, Shutdown)>)
+pub struct Listener<T, Msg, Shutdown = Infallible>
 where
-    T: ActorListener<Msg, Shutdown>;
+    T: PollListener<Msg, Shutdown>,
+{
+    listener: T,
+    paused: bool,
+    /// Polled by the actor loop and not handled yet
+    pending: Vec<ActorMsg<Msg, Shutdown>>,
+}
 
 
 impl<T, Msg, Shutdown> Deref for Listener<T, Msg, Shutdown>
 where
-    T: ActorListener<Msg, Shutdown>,
+    T: PollListener<Msg, Shutdown>,
 {
     type Target = T;
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.listener
     }
 }
 
 
 impl<T, Msg, Shutdown> DerefMut for Listener<T, Msg, Shutdown>
 where
-    T: ActorListener<Msg, Shutdown>,
+    T: PollListener<Msg, Shutdown>,
 {
     fn deref_mut(&mut self) -> &mut Self::Target {
-        &mut self.0
+        &mut self.listener
     }
 }
 
 
 impl<T, Msg, Shutdown> Listener<T, Msg, Shutdown>
 where
-    T: ActorListener<Msg, Shutdown>,
+    T: PollListener<Msg, Shutdown>,
 {
     #[inline(always)]
     pub fn new(listener: T) -> Self {
-        Listener(listener, PhantomData)
+        Listener { listener, paused: false, pending: Vec::new() }
+    }
+
+    #[inline(always)]
+    pub fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        self.listener.poll_next_msg(cx)
+    }
+
+    #[inline(always)]
+    pub fn poll_next_batch(&mut self, cx: &mut Context<'_>, batch: &mut Vec<ActorMsg<Msg, Shutdown>>, limit: usize) -> Poll<usize> {
+        self.listener.poll_next_batch(cx, batch, limit)
     }
 
     #[inline(always)]
     pub async fn next_msg(&mut self) -> Option<ActorMsg<Msg, Shutdown>> {
-        self.0.next_msg().await
+        poll_fn(|cx| self.listener.poll_next_msg(cx)).await
+    }
+
+    /// The generated actor loop stops polling a paused listener,
+    /// messages stay queued in the underlying source
+    ///
+    /// A paused listener is not closed, the actor keeps running even if every listener is paused.
+    /// Then only `idle_timeout` can stop it.
+    #[inline(always)]
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    #[inline(always)]
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    #[inline(always)]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+}
+
+/// Lets a `Listener`, as a `Mailbox`, go where a plain source is expected, such as `ActorBuilder`.
+/// Pausing is up to the actor loop, so it is not checked here.
+impl<T, Msg, Shutdown> PollListener<Msg, Shutdown> for Listener<T, Msg, Shutdown>
+where
+    T: PollListener<Msg, Shutdown>,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        self.listener.poll_next_msg(cx)
+    }
+
+    #[inline(always)]
+    fn poll_next_batch(&mut self, cx: &mut Context<'_>, batch: &mut Vec<ActorMsg<Msg, Shutdown>>, limit: usize) -> Poll<usize> {
+        self.listener.poll_next_batch(cx, batch, limit)
+    }
+}
+
+impl<T, Msg, Shutdown> LoopSource for Listener<T, Msg, Shutdown>
+where
+    T: PollListener<Msg, Shutdown>,
+{
+    type Msg = Msg;
+    type Shutdown = Shutdown;
+
+    #[inline(always)]
+    fn poll_pending(&mut self, cx: &mut Context<'_>, limit: usize) -> ListenerPoll {
+        if self.paused {
+            return ListenerPoll::Paused;
+        }
+        poll_pending(&mut self.listener, &mut self.pending, cx, limit)
+    }
+
+    #[inline(always)]
+    fn pending(&mut self) -> &mut Vec<ActorMsg<Msg, Shutdown>> {
+        &mut self.pending
     }
 }
 
+/// Delivered to `AsyncHandle<ListenerClosed>` by `#[listener(on_close = "notify")]`
+/// once the listener returned `None`, the listener is not polled anymore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ListenerClosed {
+    /// Name of the listener field
+    pub listener: &'static str,
+}
+
 /// Interval
-impl<Resp> ActorListener<Resp> for Interval
+impl<Resp, Shutdown> PollListener<Resp, Shutdown> for Interval
 where
     Resp: From<Instant>,
 {
     #[inline(always)]
-    async fn next_msg(&mut self) -> Option<ActorMsg<Resp>> {
-        Some(ActorMsg::Msg(self.tick().await.into()))
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Resp, Shutdown>>> {
+        self.poll_tick(cx).map(|instant| Some(ActorMsg::Msg(instant.into())))
     }
 }
 
 /// UnboundedReceiver
-impl<Msg, Shutdown> ActorListener<Msg, Shutdown> for UnboundedReceiver<ActorMsg<Msg, Shutdown>>
+impl<Msg, Shutdown> PollListener<Msg, Shutdown> for UnboundedReceiver<ActorMsg<Msg, Shutdown>>
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        self.poll_recv(cx)
+    }
+
+    #[inline(always)]
+    fn poll_next_batch(&mut self, cx: &mut Context<'_>, batch: &mut Vec<ActorMsg<Msg, Shutdown>>, limit: usize) -> Poll<usize> {
+        self.poll_recv_many(cx, batch, limit)
+    }
+}
+
+/// Receiver
+impl<Msg, Shutdown> PollListener<Msg, Shutdown> for Receiver<ActorMsg<Msg, Shutdown>>
 {
     #[inline(always)]
-    async fn next_msg(&mut self) -> Option<ActorMsg<Msg, Shutdown>> {
-        self.recv().await
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        self.poll_recv(cx)
+    }
+
+    #[inline(always)]
+    fn poll_next_batch(&mut self, cx: &mut Context<'_>, batch: &mut Vec<ActorMsg<Msg, Shutdown>>, limit: usize) -> Poll<usize> {
+        self.poll_recv_many(cx, batch, limit)
     }
 }
@@ -0,0 +1,107 @@
+use crate::{ActorListener, ActorMsg, Listener, PollListener};
+use std::convert::Infallible;
+use std::task::{ready, Context, Poll};
+use tokio_util::sync::ReusableBoxFuture;
+
+type NextMsg<T, Msg, Shutdown> = ReusableBoxFuture<'static, (T, Option<ActorMsg<Msg, Shutdown>>)>;
+
+/// Adapter from a future based `ActorListener` to `PollListener`
+///
+/// The pending `next_msg` future is kept between polls instead of being dropped,
+/// so listeners that are not cancel safe don't lose messages.
+/// The future allocation is reused for every message.
+pub struct FutureListener<T, Msg, Shutdown = Infallible> {
+    /// `None` while `next_msg` is in flight
+    listener: Option<T>,
+    next_msg: Option<NextMsg<T, Msg, Shutdown>>,
+}
+
+
+impl<T, Msg, Shutdown> FutureListener<T, Msg, Shutdown>
+where
+    T: ActorListener<Msg, Shutdown> + Send + 'static,
+    Msg: Send + 'static,
+    Shutdown: Send + 'static,
+{
+    #[inline(always)]
+    pub fn new(listener: T) -> Self {
+        FutureListener { listener: Some(listener), next_msg: None }
+    }
+
+    /// The inner listener, `None` while it is waiting for the next message
+    ///
+    /// The actor loop polls its listeners right after every handler, so other handlers
+    /// mostly see `None`. `WatchListener` and `BroadcastListener` keep their receiver reachable.
+    #[inline(always)]
+    pub fn get_mut(&mut self) -> Option<&mut T> {
+        self.listener.as_mut()
+    }
+}
+
+
+impl<T, Msg, Shutdown> PollListener<Msg, Shutdown> for FutureListener<T, Msg, Shutdown>
+where
+    T: ActorListener<Msg, Shutdown> + Send + 'static,
+    Msg: Send + 'static,
+    Shutdown: Send + 'static,
+{
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        if let Some(mut listener) = self.listener.take() {
+            let future = async move {
+                let msg = listener.next_msg().await;
+                (listener, msg)
+            };
+            match &mut self.next_msg {
+                Some(next_msg) => next_msg.set(future),
+                None => self.next_msg = Some(ReusableBoxFuture::new(future)),
+            }
+        }
+        let next_msg = self.next_msg.as_mut().expect("next_msg is set while listener is taken");
+        let (listener, msg) = ready!(next_msg.poll(cx));
+        self.listener = Some(listener);
+        Poll::Ready(msg)
+    }
+}
+
+
+impl<T, Msg, Shutdown> Listener<FutureListener<T, Msg, Shutdown>, Msg, Shutdown>
+where
+    T: ActorListener<Msg, Shutdown> + Send + 'static,
+    Msg: Send + 'static,
+    Shutdown: Send + 'static,
+{
+    #[inline(always)]
+    pub fn from_future(listener: T) -> Self {
+        Listener::new(FutureListener::new(listener))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tokio::time::{sleep, timeout};
+
+    /// Loses the received value if dropped during the sleep
+    struct NotCancelSafe(mpsc::UnboundedReceiver<u32>);
+
+    impl ActorListener<u32> for NotCancelSafe {
+        async fn next_msg(&mut self) -> Option<ActorMsg<u32>> {
+            let value = self.0.recv().await?;
+            sleep(Duration::from_millis(20)).await;
+            Some(ActorMsg::Msg(value))
+        }
+    }
+
+    #[tokio::test]
+    async fn pending_message_survives_interrupted_poll() {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        let mut listener: Listener<_, u32> = Listener::from_future(NotCancelSafe(receiver));
+        sender.send(5).unwrap();
+
+        assert!(timeout(Duration::from_millis(1), listener.next_msg()).await.is_err());
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(5))));
+    }
+}
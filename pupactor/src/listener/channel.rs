@@ -0,0 +1,330 @@
+use crate::{ActorListener, ActorMsg, PollListener};
+use std::future::Future;
+use std::ops::{Deref, DerefMut};
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use tokio::sync::broadcast::error::{RecvError, TryRecvError};
+use tokio::sync::{broadcast, oneshot, watch};
+use tokio_util::sync::ReusableBoxFuture;
+
+/// Number of broadcast messages skipped because the receiver was too slow
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Lagged(pub u64);
+
+/// broadcast::Receiver
+///
+/// Every value is delivered as `Msg::from(T)`. When the receiver falls behind,
+/// the skipped count is delivered as `Msg::from(Lagged)` and listening goes on.
+/// Closes once all senders are dropped. Future based, use with `FutureListener`,
+/// or `BroadcastListener` to poll it directly.
+impl<T, Msg, Shutdown> ActorListener<Msg, Shutdown> for broadcast::Receiver<T>
+where
+    T: Clone + Send,
+    Msg: From<T> + From<Lagged>,
+{
+    #[inline(always)]
+    async fn next_msg(&mut self) -> Option<ActorMsg<Msg, Shutdown>> {
+        match self.recv().await {
+            Ok(value) => Some(ActorMsg::Msg(value.into())),
+            Err(RecvError::Lagged(skipped)) => Some(ActorMsg::Msg(Lagged(skipped).into())),
+            Err(RecvError::Closed) => None,
+        }
+    }
+}
+
+/// watch::Receiver
+///
+/// Delivers the latest value on every change, intermediate values may be coalesced.
+/// Closes once the sender is dropped. Future based, use with `FutureListener`,
+/// or `WatchListener` to poll it directly and keep `borrow()` at hand.
+impl<T, Msg, Shutdown> ActorListener<Msg, Shutdown> for watch::Receiver<T>
+where
+    T: Clone + Send + Sync,
+    Msg: From<T>,
+{
+    #[inline(always)]
+    async fn next_msg(&mut self) -> Option<ActorMsg<Msg, Shutdown>> {
+        self.changed().await.ok()?;
+        let value = self.borrow_and_update().clone();
+        Some(ActorMsg::Msg(value.into()))
+    }
+}
+
+/// Poll based `broadcast::Receiver`, same messages as its `ActorListener` impl
+///
+/// The receiver is not moved into a pending future, so it stays reachable through `Deref`.
+/// Waits for new values on a resubscribed receiver, its future allocation is reused.
+pub struct BroadcastListener<T> {
+    receiver: broadcast::Receiver<T>,
+    /// Completes once something is sent after the receiver was found empty
+    waiter: ReusableBoxFuture<'static, ()>,
+    waiting: bool,
+}
+
+
+impl<T> BroadcastListener<T>
+where
+    T: Clone + Send + 'static,
+{
+    pub fn new(receiver: broadcast::Receiver<T>) -> Self {
+        BroadcastListener { receiver, waiter: ReusableBoxFuture::new(async {}), waiting: false }
+    }
+
+    #[inline(always)]
+    pub fn into_inner(self) -> broadcast::Receiver<T> {
+        self.receiver
+    }
+}
+
+
+impl<T> Deref for BroadcastListener<T> {
+    type Target = broadcast::Receiver<T>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+
+impl<T> DerefMut for BroadcastListener<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.receiver
+    }
+}
+
+
+impl<T, Msg, Shutdown> PollListener<Msg, Shutdown> for BroadcastListener<T>
+where
+    T: Clone + Send + 'static,
+    Msg: From<T> + From<Lagged>,
+{
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        loop {
+            match self.receiver.try_recv() {
+                Ok(value) => return Poll::Ready(Some(ActorMsg::Msg(value.into()))),
+                Err(TryRecvError::Lagged(skipped)) => return Poll::Ready(Some(ActorMsg::Msg(Lagged(skipped).into()))),
+                Err(TryRecvError::Closed) => return Poll::Ready(None),
+                Err(TryRecvError::Empty) => {}
+            }
+            if !self.waiting {
+                let mut waiter = self.receiver.resubscribe();
+                self.waiter.set(async move {
+                    let _ = waiter.recv().await;
+                });
+                self.waiting = true;
+                // Значение могло прийти до подписки, проверяем еще раз
+                continue;
+            }
+            ready!(self.waiter.poll(cx));
+            self.waiting = false;
+        }
+    }
+}
+
+
+/// Poll based `watch::Receiver`, same messages as its `ActorListener` impl
+///
+/// Changes are awaited on a clone of the receiver, so the receiver itself stays reachable
+/// through `Deref`: handlers can `borrow()` the current value at any time.
+pub struct WatchListener<T> {
+    receiver: watch::Receiver<T>,
+    changed: ReusableBoxFuture<'static, Result<(), watch::error::RecvError>>,
+    waiting: bool,
+}
+
+
+impl<T> WatchListener<T>
+where
+    T: Send + Sync + 'static,
+{
+    pub fn new(receiver: watch::Receiver<T>) -> Self {
+        WatchListener { receiver, changed: ReusableBoxFuture::new(async { Ok(()) }), waiting: false }
+    }
+
+    #[inline(always)]
+    pub fn into_inner(self) -> watch::Receiver<T> {
+        self.receiver
+    }
+}
+
+
+impl<T> Deref for WatchListener<T> {
+    type Target = watch::Receiver<T>;
+
+    #[inline(always)]
+    fn deref(&self) -> &Self::Target {
+        &self.receiver
+    }
+}
+
+
+impl<T> DerefMut for WatchListener<T> {
+    #[inline(always)]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.receiver
+    }
+}
+
+
+impl<T, Msg, Shutdown> PollListener<Msg, Shutdown> for WatchListener<T>
+where
+    T: Clone + Send + Sync + 'static,
+    Msg: From<T>,
+{
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        loop {
+            if !self.waiting {
+                // Клон начинает с той же версии, что и receiver
+                let mut waiter = self.receiver.clone();
+                self.changed.set(async move { waiter.changed().await });
+                self.waiting = true;
+            }
+            let changed = ready!(self.changed.poll(cx));
+            self.waiting = false;
+            // Handler мог уже прочитать изменение через `borrow_and_update`
+            match changed.and_then(|()| self.receiver.has_changed()) {
+                Ok(true) => {
+                    let value = self.receiver.borrow_and_update().clone();
+                    return Poll::Ready(Some(ActorMsg::Msg(value.into())));
+                }
+                Ok(false) => continue,
+                Err(_) => return Poll::Ready(None),
+            }
+        }
+    }
+}
+
+
+/// oneshot::Receiver
+///
+/// One time trigger: delivers the value once and stays pending afterwards.
+/// Closes if the sender is dropped without sending.
+impl<T, Msg, Shutdown> PollListener<Msg, Shutdown> for oneshot::Receiver<T>
+where
+    Msg: From<T>,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        if self.is_terminated() {
+            return Poll::Pending;
+        }
+        Pin::new(self).poll(cx).map(|value| value.ok().map(|value| ActorMsg::Msg(value.into())))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Listener;
+    use std::future::poll_fn;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use tokio::time::timeout;
+
+    #[derive(Debug, PartialEq)]
+    enum Config {
+        Value(u32),
+        Lagged(Lagged),
+    }
+
+    impl From<u32> for Config {
+        fn from(value: u32) -> Self {
+            Config::Value(value)
+        }
+    }
+
+    impl From<Lagged> for Config {
+        fn from(lagged: Lagged) -> Self {
+            Config::Lagged(lagged)
+        }
+    }
+
+    #[tokio::test]
+    async fn broadcast_reports_lag_as_message() {
+        let (sender, receiver) = broadcast::channel::<u32>(1);
+        let mut listener: Listener<_, Config> = Listener::from_future(receiver);
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        drop(sender);
+
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(Config::Lagged(Lagged(1))))));
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(Config::Value(2)))));
+        assert!(listener.next_msg().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn broadcast_listener_polls_without_future_listener() {
+        let (sender, receiver) = broadcast::channel::<u32>(1);
+        let mut listener: Listener<_, Config> = Listener::new(BroadcastListener::new(receiver));
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(Config::Lagged(Lagged(1))))));
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(Config::Value(2)))));
+        assert!(timeout(Duration::from_millis(10), listener.next_msg()).await.is_err());
+
+        sender.send(3).unwrap();
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(Config::Value(3)))));
+        assert_eq!(listener.len(), 0);
+        drop(sender);
+        assert!(listener.next_msg().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn watch_listener_keeps_borrow_reachable() {
+        let (sender, receiver) = watch::channel(0u32);
+        let mut listener: Listener<_, u32> = Listener::new(WatchListener::new(receiver));
+
+        // Pending poll, the receiver is still reachable
+        assert!(timeout(Duration::from_millis(10), listener.next_msg()).await.is_err());
+        assert_eq!(*listener.borrow(), 0);
+
+        sender.send(1).unwrap();
+        sender.send(2).unwrap();
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(2))));
+        assert_eq!(*listener.borrow(), 2);
+
+        // Seen through `borrow_and_update` already, nothing to deliver
+        sender.send(3).unwrap();
+        assert_eq!(*listener.borrow_and_update(), 3);
+        assert!(timeout(Duration::from_millis(10), listener.next_msg()).await.is_err());
+
+        drop(sender);
+        assert!(listener.next_msg().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn bounded_receiver_drains_and_closes() {
+        let (sender, receiver) = mpsc::channel::<ActorMsg<u32, &str>>(2);
+        let mut listener: Listener<_, u32, &str> = Listener::new(receiver);
+        sender.send(ActorMsg::Msg(1)).await.unwrap();
+        sender.send(ActorMsg::Msg(2)).await.unwrap();
+        // Full until the listener takes a message
+        assert!(sender.try_send(ActorMsg::Msg(3)).is_err());
+
+        let mut batch = Vec::new();
+        assert_eq!(poll_fn(|cx| listener.poll_next_batch(cx, &mut batch, 8)).await, 2);
+        assert!(matches!(batch.as_slice(), [ActorMsg::Msg(1), ActorMsg::Msg(2)]));
+
+        sender.send(ActorMsg::Shutdown("stop")).await.unwrap();
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Shutdown("stop"))));
+        assert!(timeout(Duration::from_millis(10), listener.next_msg()).await.is_err());
+
+        drop(sender);
+        assert!(listener.next_msg().await.is_none());
+        assert_eq!(poll_fn(|cx| listener.poll_next_batch(cx, &mut batch, 8)).await, 0);
+    }
+
+    #[tokio::test]
+    async fn oneshot_fires_once() {
+        let (sender, receiver) = oneshot::channel::<u32>();
+        let mut listener: Listener<_, u32> = Listener::new(receiver);
+        sender.send(7).unwrap();
+
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(7))));
+        assert!(timeout(Duration::from_millis(10), listener.next_msg()).await.is_err());
+    }
+}
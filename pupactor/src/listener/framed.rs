@@ -0,0 +1,115 @@
+use crate::{ActorMsg, Listener, PollListener};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio::io::AsyncRead;
+use tokio_stream::Stream;
+pub use tokio_util::codec::{Decoder, Framed, FramedRead, LengthDelimitedCodec, LinesCodec};
+
+/// FramedRead
+///
+/// Every decoded frame is delivered as `Msg::from(D::Item)`, decode errors as `Msg::from(D::Error)`.
+/// Closes on EOF.
+impl<R, D, Msg, Shutdown> PollListener<Msg, Shutdown> for FramedRead<R, D>
+where
+    R: AsyncRead + Unpin,
+    D: Decoder,
+    Msg: From<D::Item> + From<D::Error>,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        Pin::new(self).poll_next(cx).map(|frame| frame.map(|frame| ActorMsg::Msg(match frame {
+            Ok(frame) => frame.into(),
+            Err(err) => err.into(),
+        })))
+    }
+}
+
+/// Framed
+///
+/// Same as `FramedRead`, the write half stays available through `Deref` on the listener.
+impl<T, D, Msg, Shutdown> PollListener<Msg, Shutdown> for Framed<T, D>
+where
+    T: AsyncRead + Unpin,
+    D: Decoder,
+    Msg: From<D::Item> + From<D::Error>,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        Pin::new(self).poll_next(cx).map(|frame| frame.map(|frame| ActorMsg::Msg(match frame {
+            Ok(frame) => frame.into(),
+            Err(err) => err.into(),
+        })))
+    }
+}
+
+
+impl<R, D, Msg, Shutdown> Listener<FramedRead<R, D>, Msg, Shutdown>
+where
+    R: AsyncRead + Unpin,
+    D: Decoder,
+    Msg: From<D::Item> + From<D::Error>,
+{
+    #[inline(always)]
+    pub fn framed(reader: R, decoder: D) -> Self {
+        Listener::new(FramedRead::new(reader, decoder))
+    }
+}
+
+
+impl<R, Msg, Shutdown> Listener<FramedRead<R, LinesCodec>, Msg, Shutdown>
+where
+    R: AsyncRead + Unpin,
+    Msg: From<String> + From<<LinesCodec as Decoder>::Error>,
+{
+    /// Newline delimited text frames
+    #[inline(always)]
+    pub fn lines(reader: R) -> Self {
+        Listener::framed(reader, LinesCodec::new())
+    }
+}
+
+
+impl<R, Msg, Shutdown> Listener<FramedRead<R, LengthDelimitedCodec>, Msg, Shutdown>
+where
+    R: AsyncRead + Unpin,
+    Msg: From<<LengthDelimitedCodec as Decoder>::Item> + From<<LengthDelimitedCodec as Decoder>::Error>,
+{
+    /// Length prefixed binary frames
+    #[inline(always)]
+    pub fn length_delimited(reader: R) -> Self {
+        Listener::framed(reader, LengthDelimitedCodec::new())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio_util::codec::LinesCodecError;
+
+    enum Frame {
+        Line(String),
+        Error,
+    }
+
+    impl From<String> for Frame {
+        fn from(line: String) -> Self {
+            Frame::Line(line)
+        }
+    }
+
+    impl From<LinesCodecError> for Frame {
+        fn from(_: LinesCodecError) -> Self {
+            Frame::Error
+        }
+    }
+
+    #[tokio::test]
+    async fn lines_until_eof() {
+        let mut listener: Listener<_, Frame> = Listener::lines(&b"first\nsecond\n"[..]);
+
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(Frame::Line(line))) if line == "first"));
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(Frame::Line(line))) if line == "second"));
+        assert!(listener.next_msg().await.is_none());
+    }
+}
@@ -0,0 +1,81 @@
+use crate::{ActorMsg, PollListener};
+use std::io;
+use std::net::SocketAddr;
+use std::task::{Context, Poll};
+use tokio::net::{TcpListener, TcpStream};
+
+/// TcpListener
+///
+/// Every accepted connection is delivered as `Msg::from((TcpStream, SocketAddr))`,
+/// accept errors as `Msg::from(io::Error)`. Never closes.
+impl<Msg, Shutdown> PollListener<Msg, Shutdown> for TcpListener
+where
+    Msg: From<(TcpStream, SocketAddr)> + From<io::Error>,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        self.poll_accept(cx).map(|accepted| Some(ActorMsg::Msg(match accepted {
+            Ok(accepted) => accepted.into(),
+            Err(err) => err.into(),
+        })))
+    }
+}
+
+/// UnixListener
+///
+/// Same as `TcpListener`, delivers `Msg::from((UnixStream, unix::SocketAddr))`
+/// or `Msg::from(io::Error)`. Never closes.
+#[cfg(unix)]
+impl<Msg, Shutdown> PollListener<Msg, Shutdown> for tokio::net::UnixListener
+where
+    Msg: From<(tokio::net::UnixStream, tokio::net::unix::SocketAddr)> + From<io::Error>,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        self.poll_accept(cx).map(|accepted| Some(ActorMsg::Msg(match accepted {
+            Ok(accepted) => accepted.into(),
+            Err(err) => err.into(),
+        })))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Listener;
+
+    enum Conn {
+        Accepted(TcpStream, SocketAddr),
+        Failed(io::Error),
+    }
+
+    impl From<(TcpStream, SocketAddr)> for Conn {
+        fn from((stream, addr): (TcpStream, SocketAddr)) -> Self {
+            Conn::Accepted(stream, addr)
+        }
+    }
+
+    impl From<io::Error> for Conn {
+        fn from(err: io::Error) -> Self {
+            Conn::Failed(err)
+        }
+    }
+
+    #[tokio::test]
+    async fn accepted_connection_becomes_message() {
+        let acceptor = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = acceptor.local_addr().unwrap();
+        let mut listener: Listener<_, Conn> = Listener::new(acceptor);
+
+        let client = TcpStream::connect(addr).await.unwrap();
+        match listener.next_msg().await {
+            Some(ActorMsg::Msg(Conn::Accepted(stream, peer))) => {
+                assert_eq!(peer, client.local_addr().unwrap());
+                assert_eq!(stream.peer_addr().unwrap(), client.local_addr().unwrap());
+            }
+            Some(ActorMsg::Msg(Conn::Failed(err))) => panic!("accept failed: {}", err),
+            _ => panic!("expected an accepted connection"),
+        }
+    }
+}
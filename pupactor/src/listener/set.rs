@@ -0,0 +1,175 @@
+use crate::actor_loop::{poll_pending, ListenerPoll, LoopSource};
+use crate::{ActorMsg, BoxStream, PollListener};
+use std::convert::Infallible;
+use std::future::poll_fn;
+use std::hash::Hash;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio_stream::{Stream, StreamExt, StreamMap};
+
+/// Keyed set of sources that can change at runtime
+///
+/// Every message is delivered as `(K, Msg)`. Finished sources are removed,
+/// an empty set just waits, so the set itself never closes.
+pub struct ListenerSet<K, Msg, Shutdown = Infallible> {
+    sources: StreamMap<K, BoxStream<ActorMsg<Msg, Shutdown>>>,
+    paused: bool,
+    /// Polled by the actor loop and not handled yet
+    pending: Vec<ActorMsg<(K, Msg), Shutdown>>,
+}
+
+
+impl<K, Msg, Shutdown> Default for ListenerSet<K, Msg, Shutdown> {
+    fn default() -> Self {
+        ListenerSet { sources: StreamMap::new(), paused: false, pending: Vec::new() }
+    }
+}
+
+
+impl<K, Msg, Shutdown> ListenerSet<K, Msg, Shutdown>
+where
+    K: Hash + Eq + Clone + Unpin,
+    Msg: 'static,
+    Shutdown: 'static,
+{
+    #[inline(always)]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a source, replacing the one with the same key
+    pub fn insert<S>(&mut self, key: K, source: S)
+    where
+        S: Stream + Send + Sync + 'static,
+        Msg: From<S::Item>,
+    {
+        self.sources.insert(key, Box::pin(source.map(|item| ActorMsg::Msg(item.into()))));
+    }
+
+    /// Drops the source, messages already queued in it are lost
+    #[inline]
+    pub fn remove(&mut self, key: &K) -> bool {
+        self.sources.remove(key).is_some()
+    }
+
+    #[inline]
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.sources.contains_key(key)
+    }
+
+    #[inline]
+    pub fn keys(&self) -> impl Iterator<Item=&K> {
+        self.sources.keys()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+
+    /// Pauses the whole set, see `Listener::pause`
+    #[inline(always)]
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    #[inline(always)]
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    #[inline(always)]
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<(K, Msg), Shutdown>>> {
+        match Pin::new(&mut self.sources).poll_next(cx) {
+            Poll::Ready(Some((key, ActorMsg::Msg(msg)))) => Poll::Ready(Some(ActorMsg::Msg((key, msg)))),
+            Poll::Ready(Some((_, ActorMsg::Shutdown(shutdown)))) => Poll::Ready(Some(ActorMsg::Shutdown(shutdown))),
+            // Пустой набор ждет, пока handler не добавит новый источник
+            Poll::Ready(None) | Poll::Pending => Poll::Pending,
+        }
+    }
+
+    #[inline(always)]
+    pub fn poll_next_batch(&mut self, cx: &mut Context<'_>, batch: &mut Vec<ActorMsg<(K, Msg), Shutdown>>, limit: usize) -> Poll<usize> {
+        PollListener::poll_next_batch(self, cx, batch, limit)
+    }
+
+    #[inline(always)]
+    pub async fn next_msg(&mut self) -> Option<ActorMsg<(K, Msg), Shutdown>> {
+        poll_fn(|cx| self.poll_next_msg(cx)).await
+    }
+}
+
+
+impl<K, Msg, Shutdown> PollListener<(K, Msg), Shutdown> for ListenerSet<K, Msg, Shutdown>
+where
+    K: Hash + Eq + Clone + Unpin,
+    Msg: 'static,
+    Shutdown: 'static,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<(K, Msg), Shutdown>>> {
+        ListenerSet::poll_next_msg(self, cx)
+    }
+}
+
+
+impl<K, Msg, Shutdown> LoopSource for ListenerSet<K, Msg, Shutdown>
+where
+    K: Hash + Eq + Clone + Unpin,
+    Msg: 'static,
+    Shutdown: 'static,
+{
+    type Msg = (K, Msg);
+    type Shutdown = Shutdown;
+
+    fn poll_pending(&mut self, cx: &mut Context<'_>, limit: usize) -> ListenerPoll {
+        if self.paused {
+            return ListenerPoll::Paused;
+        }
+        // Буфер вынимается на время опроса, сам набор опрашивается как PollListener
+        let mut pending = std::mem::take(&mut self.pending);
+        let poll = poll_pending(self, &mut pending, cx, limit);
+        self.pending = pending;
+        poll
+    }
+
+    #[inline(always)]
+    fn pending(&mut self) -> &mut Vec<ActorMsg<(K, Msg), Shutdown>> {
+        &mut self.pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn messages_carry_their_key() {
+        let mut set: ListenerSet<&str, u32> = ListenerSet::new();
+        set.insert("first", tokio_stream::iter([1u32]));
+        set.insert("second", tokio_stream::iter([2u32]));
+
+        let mut received = vec![];
+        for _ in 0..2 {
+            if let Some(ActorMsg::Msg(msg)) = set.next_msg().await {
+                received.push(msg);
+            }
+        }
+        received.sort();
+        assert_eq!(received, [("first", 1), ("second", 2)]);
+
+        assert!(timeout(Duration::from_millis(10), set.next_msg()).await.is_err());
+        assert!(set.is_empty());
+    }
+}
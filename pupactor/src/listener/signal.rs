@@ -0,0 +1,72 @@
+use crate::{ActorMsg, PollListener};
+use std::convert::Infallible;
+use std::io;
+use std::task::{Context, Poll};
+use tokio::signal::unix::{signal, Signal};
+pub use tokio::signal::unix::SignalKind;
+
+/// Unix signal listener
+///
+/// Every received signal delivers a copy of `on_signal`: either a regular message
+/// (SIGHUP as a reload request) or `ActorMsg::Shutdown`, which stops the actor
+/// through its `StopActor` impl. Closes if the signal driver goes away.
+pub struct SignalListener<Msg, Shutdown = Infallible> {
+    signal: Signal,
+    on_signal: ActorMsg<Msg, Shutdown>,
+}
+
+
+impl<Msg, Shutdown> SignalListener<Msg, Shutdown> {
+    pub fn new(kind: SignalKind, on_signal: ActorMsg<Msg, Shutdown>) -> io::Result<Self> {
+        Ok(SignalListener { signal: signal(kind)?, on_signal })
+    }
+
+    #[inline]
+    pub fn msg(kind: SignalKind, msg: Msg) -> io::Result<Self> {
+        Self::new(kind, ActorMsg::Msg(msg))
+    }
+
+    #[inline]
+    pub fn shutdown(kind: SignalKind, shutdown: Shutdown) -> io::Result<Self> {
+        Self::new(kind, ActorMsg::Shutdown(shutdown))
+    }
+}
+
+
+impl<Msg, Shutdown> PollListener<Msg, Shutdown> for SignalListener<Msg, Shutdown>
+where
+    Msg: Clone,
+    Shutdown: Clone,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        self.signal.poll_recv(cx).map(|signal| signal.map(|()| self.on_signal.clone()))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Listener;
+    use std::process::Command;
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    fn raise(signal: &str) {
+        let status = Command::new("kill").arg(format!("-{}", signal)).arg(std::process::id().to_string()).status().unwrap();
+        assert!(status.success());
+    }
+
+    #[tokio::test]
+    async fn signal_delivers_message_and_shutdown() {
+        let mut reload: Listener<_, &str, u32> = Listener::new(SignalListener::msg(SignalKind::user_defined1(), "reload").unwrap());
+        let mut stop: Listener<_, &str, u32> = Listener::new(SignalListener::shutdown(SignalKind::user_defined2(), 15).unwrap());
+
+        raise("USR1");
+        assert!(matches!(timeout(Duration::from_secs(5), reload.next_msg()).await, Ok(Some(ActorMsg::Msg("reload")))));
+
+        raise("USR2");
+        assert!(matches!(timeout(Duration::from_secs(5), stop.next_msg()).await, Ok(Some(ActorMsg::Shutdown(15)))));
+    }
+}
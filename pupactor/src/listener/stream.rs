@@ -0,0 +1,75 @@
+use crate::{ActorMsg, Listener, PollListener};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tokio_stream::Stream;
+
+/// Boxed stream, handy when the stream type can not be named (async generators, combinators)
+pub type BoxStream<T> = Pin<Box<dyn Stream<Item=T> + Send + Sync + 'static>>;
+
+/// Adapter for any `Stream`: every item is converted into `ActorMsg::Msg`,
+/// the end of the stream closes the listener
+pub struct StreamListener<S>(S);
+
+
+impl<S> StreamListener<S>
+where
+    S: Stream + Unpin,
+{
+    #[inline(always)]
+    pub fn new(stream: S) -> Self {
+        StreamListener(stream)
+    }
+
+    #[inline(always)]
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+
+impl<T> StreamListener<BoxStream<T>> {
+    /// Boxes a stream that is not `Unpin` or can not be named in a struct field
+    #[inline(always)]
+    pub fn boxed(stream: impl Stream<Item=T> + Send + Sync + 'static) -> Self {
+        StreamListener(Box::pin(stream))
+    }
+}
+
+
+impl<S, Msg, Shutdown> PollListener<Msg, Shutdown> for StreamListener<S>
+where
+    S: Stream + Unpin,
+    Msg: From<S::Item>,
+{
+    #[inline(always)]
+    fn poll_next_msg(&mut self, cx: &mut Context<'_>) -> Poll<Option<ActorMsg<Msg, Shutdown>>> {
+        Pin::new(&mut self.0).poll_next(cx).map(|item| item.map(|item| ActorMsg::Msg(item.into())))
+    }
+}
+
+
+impl<S, Msg, Shutdown> Listener<StreamListener<S>, Msg, Shutdown>
+where
+    S: Stream + Unpin,
+    Msg: From<S::Item>,
+{
+    #[inline(always)]
+    pub fn from_stream(stream: S) -> Self {
+        Listener::new(StreamListener::new(stream))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn stream_items_become_messages() {
+        let mut listener: Listener<StreamListener<_>, u64> = Listener::from_stream(tokio_stream::iter([1u32, 2]));
+
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(1))));
+        assert!(matches!(listener.next_msg().await, Some(ActorMsg::Msg(2))));
+        assert!(listener.next_msg().await.is_none());
+    }
+}
@@ -1,4 +1,5 @@
-use crate::Actor;
+use crate::{Actor, Break};
+use std::borrow::Cow;
 use std::convert::Infallible;
 use std::future::Future;
 
@@ -31,3 +32,78 @@ where
         unreachable!()
     }
 }
+
+
+/// Short shutdown reason for logs and exit reports, `#[derive(ActorShutdown)]` implements it
+pub trait ShutdownReason {
+    fn reason(&self) -> Cow<'_, str>;
+}
+
+impl ShutdownReason for Infallible {
+    fn reason(&self) -> Cow<'_, str> {
+        match *self {}
+    }
+}
+
+impl ShutdownReason for Break {
+    #[inline]
+    fn reason(&self) -> Cow<'_, str> {
+        Cow::Borrowed("break")
+    }
+}
+
+/// The text itself, for `Kill(String)` and the like
+impl ShutdownReason for String {
+    #[inline]
+    fn reason(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+impl ShutdownReason for &str {
+    #[inline]
+    fn reason(&self) -> Cow<'_, str> {
+        Cow::Borrowed(self)
+    }
+}
+
+
+/// Bound of `Actor::ShutDown` and of the `ActorBuilder` shutdown: `ShutdownReason` with the `tracing`
+/// feature, so the `stop_actor` span can record the reason, implemented for every type without it
+#[cfg(feature = "tracing")]
+pub trait TraceReason: ShutdownReason {}
+
+#[cfg(feature = "tracing")]
+impl<T: ShutdownReason + ?Sized> TraceReason for T {}
+
+#[cfg(not(feature = "tracing"))]
+pub trait TraceReason {}
+
+#[cfg(not(feature = "tracing"))]
+impl<T: ?Sized> TraceReason for T {}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::{ActorShutdown, ShutdownReason};
+
+    #[derive(ActorShutdown)]
+    #[shutdown(reason = "operator request", display)]
+    struct Stop;
+
+    #[derive(ActorShutdown)]
+    enum Fatal {
+        ConnectionLost,
+        #[shutdown(reason = "bad config")]
+        #[allow(dead_code)]
+        Config(String),
+    }
+
+    #[test]
+    fn derived_reason() {
+        assert_eq!(Stop.reason(), "operator request");
+        assert_eq!(Stop.to_string(), "operator request");
+        assert_eq!(Fatal::ConnectionLost.reason(), "connection_lost");
+        assert_eq!(Fatal::Config(String::new()).reason(), "bad config");
+    }
+}
@@ -0,0 +1,102 @@
+use crate::{ActorCommand, Batch, TraceReason};
+use std::future::Future;
+
+#[cfg(feature = "tracing")]
+use tracing::Instrument;
+
+/// Runs the whole actor task in the `actor` span with the actor name and a unique id
+#[cfg(feature = "tracing")]
+pub(crate) fn actor<F: Future>(name: &'static str, run: F) -> impl Future<Output=F::Output> {
+    use std::sync::atomic::{AtomicU64, Ordering};
+    static NEXT_ACTOR_ID: AtomicU64 = AtomicU64::new(1);
+
+    let id = NEXT_ACTOR_ID.fetch_add(1, Ordering::Relaxed);
+    run.instrument(tracing::info_span!("actor", actor = name, id))
+}
+
+#[cfg(not(feature = "tracing"))]
+#[inline(always)]
+pub(crate) fn actor<F: Future>(_name: &'static str, run: F) -> F {
+    run
+}
+
+
+/// `stop_actor` span around the `StopActor` call, carries the `ShutdownReason` with the `tracing` feature
+pub(crate) struct StopSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+
+impl StopSpan {
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub(crate) fn new(shutdown: &impl TraceReason) -> Self {
+        StopSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!("stop_actor", reason = &*crate::ShutdownReason::reason(shutdown)),
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    pub(crate) fn stop<F: Future>(self, stop: F) -> impl Future<Output=F::Output> {
+        stop.instrument(self.span)
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    pub(crate) fn stop<F: Future>(self, stop: F) -> F {
+        stop
+    }
+}
+
+
+/// `message` span around one handler call, used by the actor loop and by fn actors
+///
+/// Carries the message type and records the `outcome` of the handler:
+/// `continue`, `break` or `kill`.
+#[doc(hidden)]
+pub struct MessageSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+
+impl MessageSpan {
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub fn new<Msg>(msg: &Msg) -> Self {
+        MessageSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("message", msg = std::any::type_name::<Msg>(), outcome = tracing::field::Empty),
+        }
+    }
+
+    /// Same span for a `#[listener(batch = N)]` batch, also carries the batch size
+    #[inline(always)]
+    #[allow(unused_variables)]
+    pub fn batch<Msg, Shutdown>(batch: &Batch<'_, Msg, Shutdown>) -> Self {
+        MessageSpan {
+            #[cfg(feature = "tracing")]
+            span: tracing::debug_span!("message", msg = std::any::type_name::<Msg>(), batch = batch.len(), outcome = tracing::field::Empty),
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    pub async fn handle<ShutDown>(self, handle: impl Future<Output=ActorCommand<ShutDown>>) -> ActorCommand<ShutDown> {
+        let command = handle.instrument(self.span.clone()).await;
+        let outcome = match &command.0 {
+            Ok(()) => "continue",
+            Err(Ok(_)) => "break",
+            Err(Err(_)) => "kill",
+        };
+        self.span.record("outcome", outcome);
+        command
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    #[inline(always)]
+    pub async fn handle<ShutDown>(self, handle: impl Future<Output=ActorCommand<ShutDown>>) -> ActorCommand<ShutDown> {
+        handle.await
+    }
+}
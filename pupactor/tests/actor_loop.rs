@@ -0,0 +1,562 @@
+#![allow(refining_impl_trait)]
+
+use pupactor::{Actor, ActorRef, AsyncHandle, Break, Continue, Handle, HandleBatch, ListenerClosed, ListenerSet, Mailbox, Pupactor};
+use std::time::Duration;
+use tokio::time::timeout;
+
+async fn run<Act: Actor>(actor: &mut Act) -> Result<Break, Act::ShutDown> {
+    timeout(Duration::from_secs(5), actor.infinite_loop()).await.expect("actor did not stop")
+}
+
+
+#[derive(Pupactor)]
+struct BreakOnClose {
+    #[listener]
+    mailbox: Mailbox<u32>,
+    received: Vec<u32>,
+}
+
+impl Handle<u32> for BreakOnClose {
+    fn handle(&mut self, value: u32) {
+        self.received.push(value);
+    }
+}
+
+#[tokio::test]
+async fn closed_listener_breaks_by_default() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    let mut actor = BreakOnClose { mailbox, received: vec![] };
+    actor_ref.send(1u32);
+    drop(actor_ref);
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.received, [1]);
+}
+
+
+#[derive(Pupactor)]
+#[actor(select = "biased")]
+struct DisableOnClose {
+    #[listener(on_close = "disable")]
+    first: Mailbox<u32>,
+    #[listener]
+    second: Mailbox<u64>,
+    received: Vec<u64>,
+}
+
+impl Handle<u32> for DisableOnClose {
+    fn handle(&mut self, value: u32) {
+        self.received.push(value as u64);
+    }
+}
+
+impl Handle<u64> for DisableOnClose {
+    fn handle(&mut self, value: u64) {
+        self.received.push(value);
+    }
+}
+
+#[tokio::test]
+async fn disabled_listener_keeps_actor_running() {
+    let (first_ref, first) = ActorRef::channel();
+    let (second_ref, second) = ActorRef::channel();
+    let mut actor = DisableOnClose { first, second, received: vec![] };
+    first_ref.send(1u32);
+    drop(first_ref);
+
+    let actor_task = tokio::spawn(async move {
+        let stop = run(&mut actor).await;
+        (stop.is_ok(), actor.received)
+    });
+    // The first listener is closed by now, the actor still handles the second one
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    second_ref.send(2u64);
+    drop(second_ref);
+
+    assert_eq!(actor_task.await.unwrap(), (true, vec![1, 2]));
+}
+
+
+#[derive(Pupactor)]
+struct NotifyOnClose {
+    #[listener(on_close = "notify")]
+    mailbox: Mailbox<u32>,
+    closed: Option<&'static str>,
+}
+
+impl Handle<u32> for NotifyOnClose {
+    fn handle(&mut self, _: u32) {}
+}
+
+impl Handle<ListenerClosed> for NotifyOnClose {
+    fn handle(&mut self, closed: ListenerClosed) -> Break {
+        self.closed = Some(closed.listener);
+        Break
+    }
+}
+
+#[tokio::test]
+async fn notify_delivers_listener_closed() {
+    let (actor_ref, mailbox) = ActorRef::<u32>::channel();
+    let mut actor = NotifyOnClose { mailbox, closed: None };
+    drop(actor_ref);
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.closed, Some("mailbox"));
+}
+
+
+/// Same message type on two listeners, `urgent` goes to its own method
+#[derive(Pupactor)]
+#[actor(select = "biased")]
+struct RoutedHandler {
+    #[listener(handler = "on_urgent", on_close = "disable")]
+    urgent: Mailbox<u32>,
+    #[listener]
+    normal: Mailbox<u32>,
+    normal_received: Vec<u32>,
+    urgent_received: Vec<u32>,
+}
+
+impl RoutedHandler {
+    async fn on_urgent(&mut self, value: u32) {
+        self.urgent_received.push(value);
+    }
+}
+
+impl Handle<u32> for RoutedHandler {
+    fn handle(&mut self, value: u32) {
+        self.normal_received.push(value);
+    }
+}
+
+#[tokio::test]
+async fn listener_handler_routes_to_the_named_method() {
+    let (urgent_ref, urgent) = ActorRef::channel();
+    let (normal_ref, normal) = ActorRef::channel();
+    for value in 1..=2u32 {
+        urgent_ref.send(value * 10);
+        normal_ref.send(value);
+    }
+    drop(urgent_ref);
+    drop(normal_ref);
+    let mut actor = RoutedHandler { urgent, normal, normal_received: vec![], urgent_received: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.urgent_received, [10, 20]);
+    assert_eq!(actor.normal_received, [1, 2]);
+}
+
+
+/// Records `u32` messages as is and `u64` messages as `value * 10`, stops after six
+#[derive(Pupactor)]
+#[actor(select = "round_robin")]
+struct RoundRobin {
+    #[listener]
+    first: Mailbox<u32>,
+    #[listener]
+    second: Mailbox<u64>,
+    received: Vec<u64>,
+}
+
+impl Handle<u32> for RoundRobin {
+    fn handle(&mut self, value: u32) -> Option<Break> {
+        self.received.push(value as u64);
+        (self.received.len() == 6).then_some(Break)
+    }
+}
+
+impl Handle<u64> for RoundRobin {
+    fn handle(&mut self, value: u64) -> Option<Break> {
+        self.received.push(value * 10);
+        (self.received.len() == 6).then_some(Break)
+    }
+}
+
+#[tokio::test]
+async fn round_robin_interleaves_ready_listeners() {
+    let (first_ref, first) = ActorRef::channel();
+    let (second_ref, second) = ActorRef::channel();
+    for value in 1..=3 {
+        first_ref.send(value as u32);
+        second_ref.send(value as u64);
+    }
+    let mut actor = RoundRobin { first, second, received: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.received, [1, 10, 2, 20, 3, 30]);
+}
+
+
+/// `RoundRobin` with an empty listener after the busy ones
+#[derive(Pupactor)]
+#[actor(select = "round_robin")]
+struct RoundRobinWithEmpty {
+    #[listener]
+    first: Mailbox<u32>,
+    #[listener]
+    second: Mailbox<u64>,
+    #[listener]
+    empty: Mailbox<u16>,
+    received: Vec<u64>,
+}
+
+impl Handle<u32> for RoundRobinWithEmpty {
+    fn handle(&mut self, value: u32) -> Option<Break> {
+        self.received.push(value as u64);
+        (self.received.len() == 6).then_some(Break)
+    }
+}
+
+impl Handle<u64> for RoundRobinWithEmpty {
+    fn handle(&mut self, value: u64) -> Option<Break> {
+        self.received.push(value * 10);
+        (self.received.len() == 6).then_some(Break)
+    }
+}
+
+impl Handle<u16> for RoundRobinWithEmpty {
+    fn handle(&mut self, _: u16) {
+        unreachable!("nothing is sent to the empty listener");
+    }
+}
+
+#[tokio::test]
+async fn round_robin_skips_empty_listener_fairly() {
+    let (first_ref, first) = ActorRef::channel();
+    let (second_ref, second) = ActorRef::channel();
+    let (_empty_ref, empty) = ActorRef::channel();
+    for value in 1..=3 {
+        first_ref.send(value as u32);
+        second_ref.send(value as u64);
+    }
+    let mut actor = RoundRobinWithEmpty { first, second, empty, received: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.received, [1, 10, 2, 20, 3, 30]);
+}
+
+
+#[derive(Pupactor)]
+struct Priority {
+    #[listener]
+    normal: Mailbox<u32>,
+    #[listener(priority = 10)]
+    urgent: Mailbox<u64>,
+    received: Vec<u64>,
+}
+
+impl Handle<u32> for Priority {
+    fn handle(&mut self, value: u32) -> Option<Break> {
+        self.received.push(value as u64);
+        (self.received.len() == 6).then_some(Break)
+    }
+}
+
+impl Handle<u64> for Priority {
+    fn handle(&mut self, value: u64) -> Option<Break> {
+        self.received.push(value * 10);
+        (self.received.len() == 6).then_some(Break)
+    }
+}
+
+#[tokio::test]
+async fn high_priority_listener_is_drained_first() {
+    let (normal_ref, normal) = ActorRef::channel();
+    let (urgent_ref, urgent) = ActorRef::channel();
+    for value in 1..=3 {
+        normal_ref.send(value as u32);
+        urgent_ref.send(value as u64);
+    }
+    let mut actor = Priority { normal, urgent, received: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.received, [10, 20, 30, 1, 2, 3]);
+}
+
+
+enum Control {
+    Pause,
+    Resume,
+}
+
+#[derive(Pupactor)]
+#[actor(select = "biased")]
+struct Pausable {
+    #[listener]
+    control: Mailbox<Control>,
+    #[listener]
+    data: Mailbox<u32>,
+    seen: tokio::sync::mpsc::UnboundedSender<u32>,
+}
+
+impl Handle<Control> for Pausable {
+    fn handle(&mut self, control: Control) {
+        match control {
+            Control::Pause => self.data.pause(),
+            Control::Resume => self.data.resume(),
+        }
+    }
+}
+
+impl Handle<u32> for Pausable {
+    fn handle(&mut self, value: u32) {
+        let _ = self.seen.send(value);
+    }
+}
+
+#[tokio::test]
+async fn paused_listener_keeps_messages_until_resume() {
+    let (control_ref, control) = ActorRef::channel();
+    let (data_ref, data) = ActorRef::channel();
+    let (seen, mut seen_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut actor = Pausable { control, data, seen };
+    control_ref.send(Control::Pause);
+    data_ref.send(1u32);
+    data_ref.send(2u32);
+    let actor_task = tokio::spawn(async move { run(&mut actor).await.is_ok() });
+
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(seen_receiver.try_recv().is_err());
+
+    control_ref.send(Control::Resume);
+    assert_eq!(seen_receiver.recv().await, Some(1));
+    assert_eq!(seen_receiver.recv().await, Some(2));
+
+    drop(control_ref);
+    assert!(actor_task.await.unwrap());
+}
+
+
+#[derive(Pupactor)]
+struct PausesItself {
+    #[listener]
+    mailbox: Mailbox<u32>,
+}
+
+impl Handle<u32> for PausesItself {
+    fn handle(&mut self, _: u32) {
+        self.mailbox.pause();
+    }
+}
+
+#[tokio::test]
+async fn actor_with_every_listener_paused_keeps_running() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    let mut actor = PausesItself { mailbox };
+    actor_ref.send(1u32);
+
+    assert!(timeout(Duration::from_millis(50), actor.infinite_loop()).await.is_err());
+    assert!(actor.mailbox.is_paused());
+}
+
+
+enum Feed {
+    Add(&'static str, tokio::sync::mpsc::UnboundedReceiver<u32>),
+    Remove(&'static str),
+}
+
+#[derive(Pupactor)]
+#[actor(select = "biased")]
+struct Feeds {
+    #[listener]
+    control: Mailbox<Feed>,
+    #[listener]
+    feeds: ListenerSet<&'static str, u32>,
+    seen: tokio::sync::mpsc::UnboundedSender<(&'static str, u32)>,
+}
+
+impl Handle<Feed> for Feeds {
+    fn handle(&mut self, feed: Feed) {
+        match feed {
+            Feed::Add(key, receiver) => self.feeds.insert(key, tokio_stream::wrappers::UnboundedReceiverStream::new(receiver)),
+            Feed::Remove(key) => {
+                self.feeds.remove(&key);
+            }
+        }
+    }
+}
+
+impl Handle<(&'static str, u32)> for Feeds {
+    fn handle(&mut self, msg: (&'static str, u32)) {
+        let _ = self.seen.send(msg);
+    }
+}
+
+#[tokio::test]
+async fn handlers_add_and_remove_listener_set_sources() {
+    let (control_ref, control) = ActorRef::channel();
+    let (seen, mut seen_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let mut actor = Feeds { control, feeds: ListenerSet::new(), seen };
+    let actor_task = tokio::spawn(async move { run(&mut actor).await.is_ok() });
+
+    let (first, first_receiver) = tokio::sync::mpsc::unbounded_channel();
+    let (second, second_receiver) = tokio::sync::mpsc::unbounded_channel();
+    control_ref.send(Feed::Add("first", first_receiver));
+    control_ref.send(Feed::Add("second", second_receiver));
+    first.send(1).unwrap();
+    second.send(10).unwrap();
+    let mut received = vec![seen_receiver.recv().await.unwrap(), seen_receiver.recv().await.unwrap()];
+    received.sort();
+    assert_eq!(received, [("first", 1), ("second", 10)]);
+
+    control_ref.send(Feed::Remove("second"));
+    first.send(2).unwrap();
+    assert_eq!(seen_receiver.recv().await, Some(("first", 2)));
+    // The removed source is dropped together with its receiver
+    assert!(second.send(20).is_err());
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(seen_receiver.try_recv().is_err());
+
+    drop(control_ref);
+    assert!(actor_task.await.unwrap());
+}
+
+
+/// Handles a message slower than its `idle_timeout`
+#[derive(Pupactor)]
+#[actor(idle_timeout = "100ms")]
+struct SlowHandler {
+    #[listener]
+    mailbox: Mailbox<u32>,
+    received: Vec<u32>,
+}
+
+impl AsyncHandle<u32> for SlowHandler {
+    async fn async_handle(&mut self, value: u32) -> Continue {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        self.received.push(value);
+    }
+}
+
+#[tokio::test]
+async fn idle_timeout_counts_from_the_end_of_a_slow_handler() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    let mut actor = SlowHandler { mailbox, received: vec![] };
+    actor_ref.send(1u32);
+    let actor_task = tokio::spawn(async move {
+        let stop = run(&mut actor).await;
+        (stop.is_ok(), actor.received)
+    });
+    // The first handler is done by now, the actor has been idle for 20ms only
+    tokio::time::sleep(Duration::from_millis(220)).await;
+    actor_ref.send(2u32);
+
+    assert_eq!(actor_task.await.unwrap(), (true, vec![1, 2]));
+}
+
+
+#[derive(Pupactor)]
+struct BatchFallback {
+    #[listener(batch = 4)]
+    mailbox: Mailbox<u32>,
+    received: Vec<u32>,
+}
+
+impl Handle<u32> for BatchFallback {
+    fn handle(&mut self, value: u32) -> Option<Break> {
+        self.received.push(value);
+        (self.received.len() == 3).then_some(Break)
+    }
+}
+
+#[tokio::test]
+async fn batch_without_handle_batch_goes_to_handle() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    for value in 1..=3 {
+        actor_ref.send(value as u32);
+    }
+    let mut actor = BatchFallback { mailbox, received: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.received, [1, 2, 3]);
+}
+
+
+#[derive(Pupactor)]
+struct Batched {
+    #[listener(batch = 2)]
+    mailbox: Mailbox<u32>,
+    batches: Vec<Vec<u32>>,
+}
+
+impl Handle<u32> for Batched {
+    fn handle(&mut self, _: u32) {
+        unreachable!("HandleBatch takes every batch");
+    }
+}
+
+impl HandleBatch<u32> for Batched {
+    async fn handle_batch(&mut self, batch: impl ExactSizeIterator<Item=u32> + Send) -> Continue {
+        self.batches.push(batch.collect());
+    }
+}
+
+#[tokio::test]
+async fn handle_batch_receives_up_to_n_messages() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    for value in 1..=3 {
+        actor_ref.send(value as u32);
+    }
+    drop(actor_ref);
+    let mut actor = Batched { mailbox, batches: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.batches, [vec![1, 2], vec![3]]);
+}
+
+
+/// Shutdown without `ShutdownReason`, only the `tracing` feature asks for it
+#[cfg(not(feature = "tracing"))]
+mod plain_shutdown {
+    use super::*;
+    use pupactor::{ActorBuilder, Kill, StopActor};
+
+    #[derive(Debug, PartialEq)]
+    enum Stop {
+        Deploy,
+    }
+
+    #[derive(Pupactor)]
+    #[actor(shutdown = "Stop")]
+    struct PlainShutdown {
+        #[listener]
+        mailbox: Mailbox<u32, Stop>,
+    }
+
+    impl Handle<u32> for PlainShutdown {
+        fn handle(&mut self, _: u32) -> Kill<Stop> {
+            Kill(Stop::Deploy)
+        }
+    }
+
+    impl StopActor<Stop> for PlainShutdown {
+        async fn stop_actor(self, _: Stop) {}
+    }
+
+    #[tokio::test]
+    async fn derive_accepts_plain_shutdown() {
+        let (actor_ref, mailbox) = ActorRef::channel();
+        let mut actor = PlainShutdown { mailbox };
+        actor_ref.send(1u32);
+
+        assert_eq!(run(&mut actor).await.err(), Some(Stop::Deploy));
+    }
+
+    #[tokio::test]
+    async fn builder_accepts_plain_shutdown() {
+        let (actor_ref, mailbox) = ActorRef::<u32, Stop>::channel();
+        let (stopped, stopped_receiver) = tokio::sync::oneshot::channel();
+        actor_ref.send(1u32);
+
+        ActorBuilder::with_shutdown(())
+            .listener(mailbox, |_, _: u32| Box::pin(async { Kill(Stop::Deploy) }))
+            .on_stop(|_, stop| async move {
+                let _ = stopped.send(stop.err());
+            })
+            .run()
+            .await;
+
+        assert_eq!(stopped_receiver.await, Ok(Some(Stop::Deploy)));
+    }
+}
@@ -0,0 +1,94 @@
+use pupactor::{handlers, Actor, AskError, Break, Mailbox, Pupactor};
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use tokio::time::timeout;
+
+
+#[derive(Pupactor)]
+struct Counter {
+    count: u64,
+    log: Vec<String>,
+    #[listener]
+    mailbox: Mailbox<CounterMsg>,
+}
+
+#[handlers]
+impl Counter {
+    async fn on_add(&mut self, value: u64) {
+        self.count += value;
+    }
+
+    async fn on_add_scaled(&mut self, value: u64, scale: u64) {
+        self.count += value * scale;
+    }
+
+    async fn on_log(&mut self, line: String) {
+        self.log.push(line);
+    }
+
+    #[ask]
+    async fn on_get(&mut self) -> u64 {
+        self.count
+    }
+
+    #[ask]
+    async fn on_describe(&mut self, prefix: String) -> String {
+        format!("{}{} after {:?}", prefix, self.count, self.log)
+    }
+
+    async fn on_stop(&mut self) -> Break {
+        Break
+    }
+}
+
+
+/// The client and the running actor, the task returns the final count
+fn spawn_counter() -> (CounterClient, JoinHandle<(bool, u64)>) {
+    let (client, mailbox) = CounterClient::channel();
+    let actor = tokio::spawn(async move {
+        let mut counter = Counter { count: 0, log: vec![], mailbox };
+        let stop = timeout(Duration::from_secs(5), counter.infinite_loop()).await.expect("actor did not stop");
+        (stop.is_ok(), counter.count)
+    });
+    (client, actor)
+}
+
+
+#[tokio::test]
+async fn client_sends_and_asks() {
+    let (client, actor) = spawn_counter();
+
+    client.add(2);
+    client.add_scaled(3, 10);
+    client.log("added".to_string());
+    assert_eq!(client.get().await, Ok(32));
+    assert_eq!(client.describe("count ".to_string()).await, Ok("count 32 after [\"added\"]".to_string()));
+
+    client.stop();
+    assert_eq!(actor.await.unwrap(), (true, 32));
+}
+
+
+#[tokio::test]
+async fn message_enum_is_dispatched_to_handlers() {
+    let (client, actor) = spawn_counter();
+
+    client.actor_ref().send(CounterMsg::Add(4));
+    client.actor_ref().send(CounterMsg::AddScaled(1, 2));
+    assert_eq!(client.actor_ref().ask_with(CounterMsg::Get).await, Ok(6));
+
+    client.actor_ref().send(CounterMsg::Stop());
+    assert_eq!(actor.await.unwrap(), (true, 6));
+}
+
+
+#[tokio::test]
+async fn ask_after_stop_fails() {
+    let (client, actor) = spawn_counter();
+
+    client.add(1);
+    client.stop();
+    assert_eq!(actor.await.unwrap(), (true, 1));
+    assert_eq!(client.get().await, Err(AskError));
+    assert_eq!(client.describe(String::new()).await, Err(AskError));
+}
@@ -0,0 +1,302 @@
+#![allow(refining_impl_trait)]
+
+use pupactor::{Actor, ActorMsgHandle, ActorRef, AskError, Break, Handle, Mailbox, Pupactor};
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::oneshot;
+use tokio::time::timeout;
+
+async fn run<Act: Actor>(actor: &mut Act) -> Result<Break, Act::ShutDown> {
+    timeout(Duration::from_secs(5), actor.infinite_loop()).await.expect("actor did not stop")
+}
+
+
+/// What `Recorder` handlers received
+#[derive(Debug, PartialEq)]
+enum Seen {
+    Value(u32),
+    Pair(u32, String),
+    Resize(u32, u32),
+    Reset,
+    Log(String, u8),
+}
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "Recorder")]
+enum RecorderMsg {
+    #[handle(from)]
+    Value(u32),
+    Pair(u32, String),
+    Resize { width: u32, height: u32 },
+    Reset,
+    #[handle(with = "on_log")]
+    Log(String, u8),
+    Stop,
+}
+
+#[derive(Pupactor)]
+struct Recorder {
+    #[listener]
+    mailbox: Mailbox<RecorderMsg>,
+    seen: Vec<Seen>,
+}
+
+impl Recorder {
+    async fn on_log(&mut self, line: String, level: u8) {
+        self.seen.push(Seen::Log(line, level));
+    }
+}
+
+impl Handle<u32> for Recorder {
+    fn handle(&mut self, value: u32) {
+        self.seen.push(Seen::Value(value));
+    }
+}
+
+impl Handle<(u32, String)> for Recorder {
+    fn handle(&mut self, (value, text): (u32, String)) {
+        self.seen.push(Seen::Pair(value, text));
+    }
+}
+
+impl Handle<(u32, u32)> for Recorder {
+    fn handle(&mut self, (width, height): (u32, u32)) {
+        self.seen.push(Seen::Resize(width, height));
+    }
+}
+
+impl Handle<RecorderMsgReset> for Recorder {
+    fn handle(&mut self, _: RecorderMsgReset) {
+        self.seen.push(Seen::Reset);
+    }
+}
+
+impl Handle<RecorderMsgStop> for Recorder {
+    fn handle(&mut self, _: RecorderMsgStop) -> Break {
+        Break
+    }
+}
+
+#[tokio::test]
+async fn every_variant_kind_reaches_its_handler() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    actor_ref.send(RecorderMsg::Value(1));
+    actor_ref.send(RecorderMsg::Pair(2, "two".to_string()));
+    actor_ref.send(RecorderMsg::Resize { width: 3, height: 4 });
+    actor_ref.send(RecorderMsg::Reset);
+    actor_ref.send(RecorderMsg::Log("five".to_string(), 5));
+    actor_ref.send(RecorderMsg::Stop);
+    actor_ref.send(RecorderMsg::Value(6));
+    let mut actor = Recorder { mailbox, seen: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.seen, [
+        Seen::Value(1),
+        Seen::Pair(2, "two".to_string()),
+        Seen::Resize(3, 4),
+        Seen::Reset,
+        Seen::Log("five".to_string(), 5),
+    ]);
+}
+
+
+#[tokio::test]
+async fn variant_from_converts_the_inner_value() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    actor_ref.send(5u32);
+    actor_ref.send(RecorderMsg::Stop);
+    let mut actor = Recorder { mailbox, seen: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.seen, [Seen::Value(5)]);
+}
+
+
+/// Converts every message into text, stops on `Flush`
+#[derive(ActorMsgHandle)]
+#[actor(kind = "Converter", from)]
+enum ConverterMsg {
+    Number(u64),
+    Text { text: String },
+    // Two fields, skipped by the enum-wide `from`
+    Pair(u8, u8),
+    Flush,
+}
+
+#[derive(Pupactor)]
+struct Converter {
+    #[listener]
+    mailbox: Mailbox<ConverterMsg>,
+    converted: Vec<String>,
+}
+
+impl Handle<u64> for Converter {
+    fn handle(&mut self, value: u64) {
+        self.converted.push(value.to_string());
+    }
+}
+
+impl Handle<String> for Converter {
+    fn handle(&mut self, text: String) {
+        self.converted.push(text);
+    }
+}
+
+impl Handle<(u8, u8)> for Converter {
+    fn handle(&mut self, (first, second): (u8, u8)) {
+        self.converted.push(format!("{}:{}", first, second));
+    }
+}
+
+impl Handle<ConverterMsgFlush> for Converter {
+    fn handle(&mut self, _: ConverterMsgFlush) -> Break {
+        Break
+    }
+}
+
+#[tokio::test]
+async fn enum_from_converts_single_field_and_unit_variants() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    actor_ref.send(7u64);
+    actor_ref.send("text".to_string());
+    actor_ref.send(ConverterMsg::Pair(1, 2));
+    actor_ref.send(ConverterMsgFlush);
+    let mut actor = Converter { mailbox, converted: vec![] };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.converted, ["7", "text", "1:2"]);
+}
+
+
+/// Backend a `Worker` is generic over
+trait Storage: Send + Sync + 'static {
+    fn put(&mut self, key: String, value: u64);
+}
+
+impl Storage for Vec<(String, u64)> {
+    fn put(&mut self, key: String, value: u64) {
+        self.push((key, value));
+    }
+}
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "Worker<S>", generics = "<S: Storage>")]
+enum WorkerMsg<T>
+where
+    T: Into<u64>,
+{
+    Put(String, u64),
+    Custom(T),
+    Stop,
+}
+
+#[derive(Pupactor)]
+struct Worker<S>
+where
+    S: Storage,
+{
+    storage: S,
+    #[listener]
+    mailbox: Mailbox<WorkerMsg<u32>>,
+}
+
+impl<S> Handle<(String, u64)> for Worker<S>
+where
+    S: Storage,
+{
+    fn handle(&mut self, (key, value): (String, u64)) {
+        self.storage.put(key, value);
+    }
+}
+
+impl<S> Handle<u32> for Worker<S>
+where
+    S: Storage,
+{
+    fn handle(&mut self, value: u32) {
+        self.storage.put("custom".to_string(), value.into());
+    }
+}
+
+impl<S> Handle<WorkerMsgStop> for Worker<S>
+where
+    S: Storage,
+{
+    fn handle(&mut self, _: WorkerMsgStop) -> Break {
+        Break
+    }
+}
+
+#[tokio::test]
+async fn generic_actor_handles_generic_messages() {
+    let (actor_ref, mailbox) = ActorRef::channel();
+    actor_ref.send(WorkerMsg::Put("put".to_string(), 1));
+    actor_ref.send(WorkerMsg::Custom(2u32));
+    actor_ref.send(WorkerMsg::Stop);
+    let mut actor = Worker { storage: Vec::new(), mailbox };
+
+    assert!(run(&mut actor).await.is_ok());
+    assert_eq!(actor.storage, [("put".to_string(), 1), ("custom".to_string(), 2)]);
+}
+
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "Store", client = "StoreClient")]
+enum StoreMsg {
+    Put(String, u64),
+    Get(String, oneshot::Sender<Option<u64>>),
+    Len { reply: oneshot::Sender<usize> },
+    #[handle(client = "halt")]
+    Stop,
+}
+
+#[derive(Pupactor)]
+struct Store {
+    #[listener]
+    mailbox: Mailbox<StoreMsg>,
+    values: HashMap<String, u64>,
+}
+
+impl Handle<(String, u64)> for Store {
+    fn handle(&mut self, (key, value): (String, u64)) {
+        self.values.insert(key, value);
+    }
+}
+
+impl Handle<(String, oneshot::Sender<Option<u64>>)> for Store {
+    fn handle(&mut self, (key, reply): (String, oneshot::Sender<Option<u64>>)) {
+        let _ = reply.send(self.values.get(&key).copied());
+    }
+}
+
+impl Handle<oneshot::Sender<usize>> for Store {
+    fn handle(&mut self, reply: oneshot::Sender<usize>) {
+        let _ = reply.send(self.values.len());
+    }
+}
+
+impl Handle<StoreMsgStop> for Store {
+    fn handle(&mut self, _: StoreMsgStop) -> Break {
+        Break
+    }
+}
+
+#[tokio::test]
+async fn typed_client_sends_asks_and_fails_after_stop() {
+    let (client, mailbox) = StoreClient::channel();
+    let actor = tokio::spawn(async move {
+        let mut store = Store { mailbox, values: HashMap::new() };
+        run(&mut store).await.is_ok()
+    });
+
+    client.put("a".to_string(), 1);
+    client.put("b".to_string(), 2);
+    assert_eq!(client.get("a".to_string()).await, Ok(Some(1)));
+    assert_eq!(client.get("c".to_string()).await, Ok(None));
+    assert_eq!(client.len().await, Ok(2));
+
+    client.halt();
+    assert!(actor.await.unwrap());
+    assert_eq!(client.get("a".to_string()).await, Err(AskError));
+    assert_eq!(client.len().await, Err(AskError));
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "tracing")]
+#![allow(refining_impl_trait)]
+
+use pupactor::{spawn_stateless, ActorBuilder, ActorRef, ActorShutdown, BoxedActor, Break, Handle, Kill, Mailbox, Pupactor, StopActor};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+type SpanFields = HashMap<&'static str, String>;
+
+/// Name and fields of every span created while the guard of `capture` is alive
+#[derive(Clone, Default)]
+struct Spans {
+    spans: Arc<Mutex<Vec<(&'static str, SpanFields)>>>,
+    ids: Arc<Mutex<HashMap<u64, usize>>>,
+}
+
+impl Spans {
+    fn capture() -> (Self, tracing::subscriber::DefaultGuard) {
+        let spans = Spans::default();
+        let guard = tracing::subscriber::set_default(Registry::default().with(spans.clone()));
+        (spans, guard)
+    }
+
+    /// Fields of the spans with this name, in creation order
+    fn named(&self, name: &str) -> Vec<SpanFields> {
+        let spans = self.spans.lock().unwrap();
+        spans.iter().filter(|(span, _)| *span == name).map(|(_, fields)| fields.clone()).collect()
+    }
+}
+
+struct Fields<'a>(&'a mut SpanFields);
+
+impl Visit for Fields<'_> {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name(), value.to_string());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0.insert(field.name(), format!("{:?}", value));
+    }
+}
+
+impl<S> Layer<S> for Spans
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, _: Context<'_, S>) {
+        let mut fields = HashMap::new();
+        attrs.record(&mut Fields(&mut fields));
+        let mut spans = self.spans.lock().unwrap();
+        self.ids.lock().unwrap().insert(id.into_u64(), spans.len());
+        spans.push((attrs.metadata().name(), fields));
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, _: Context<'_, S>) {
+        let Some(&index) = self.ids.lock().unwrap().get(&id.into_u64()) else {
+            return;
+        };
+        values.record(&mut Fields(&mut self.spans.lock().unwrap()[index].1));
+    }
+}
+
+
+#[derive(ActorShutdown)]
+enum CounterStop {
+    #[shutdown(reason = "limit reached")]
+    Limit,
+}
+
+#[derive(Pupactor)]
+#[actor(shutdown = "CounterStop", name = "counter")]
+struct Counter {
+    #[listener]
+    mailbox: Mailbox<u32, CounterStop>,
+}
+
+impl Handle<u32> for Counter {
+    fn handle(&mut self, value: u32) -> Option<Kill<CounterStop>> {
+        (value == 2).then_some(Kill(CounterStop::Limit))
+    }
+}
+
+impl StopActor<Break> for Counter {
+    async fn stop_actor(self, _: Break) {}
+}
+
+impl StopActor<CounterStop> for Counter {
+    async fn stop_actor(self, _: CounterStop) {}
+}
+
+#[tokio::test]
+async fn derived_actor_spans() {
+    let (spans, _guard) = Spans::capture();
+    let (actor_ref, mailbox) = ActorRef::channel();
+    actor_ref.send(1u32);
+    actor_ref.send(2u32);
+
+    BoxedActor::new(Counter { mailbox }).await;
+
+    let actors = spans.named("actor");
+    assert_eq!(actors.len(), 1);
+    assert_eq!(actors[0]["actor"], "counter");
+    let messages = spans.named("message");
+    let outcomes = messages.iter().map(|fields| (fields["msg"].as_str(), fields["outcome"].as_str())).collect::<Vec<_>>();
+    assert_eq!(outcomes, [("u32", "continue"), ("u32", "kill")]);
+    let stops = spans.named("stop_actor");
+    assert_eq!(stops.len(), 1);
+    assert_eq!(stops[0]["reason"], "limit reached");
+}
+
+#[tokio::test]
+async fn builder_spans() {
+    let (spans, _guard) = Spans::capture();
+    let (actor_ref, mailbox) = ActorRef::<u32, String>::channel();
+    actor_ref.send(7u32);
+
+    ActorBuilder::with_shutdown(())
+        .name("builder")
+        .listener(mailbox, |_, value: u32| Box::pin(async move { Kill(format!("killed by {}", value)) }))
+        .on_stop(|_, _| async {})
+        .run()
+        .await;
+
+    assert_eq!(spans.named("actor")[0]["actor"], "builder");
+    assert_eq!(spans.named("message")[0]["outcome"], "kill");
+    assert_eq!(spans.named("stop_actor")[0]["reason"], "killed by 7");
+}
+
+#[tokio::test]
+async fn fn_actor_spans() {
+    let (spans, _guard) = Spans::capture();
+    let (actor_ref, handle): (ActorRef<u32>, _) = spawn_stateless("stateless", |value: u32| async move { (value == 1).then_some(Break) });
+    actor_ref.send(1u32);
+
+    assert!(matches!(handle.await, Ok(Ok(Break))));
+    assert_eq!(spans.named("actor")[0]["actor"], "stateless");
+    assert_eq!(spans.named("message")[0]["outcome"], "break");
+}
@@ -0,0 +1,7 @@
+use pupactor::Pupactor;
+
+#[derive(Pupactor)]
+#[actor(idle_timeout = "soon")]
+struct MyActor {}
+
+fn main() {}
@@ -0,0 +1,14 @@
+struct Counter;
+
+#[pupactor::handlers]
+impl Counter {
+    async fn on_name(&mut self, name: &str) {
+        let _ = name;
+    }
+
+    async fn on_values(&mut self, values: Vec<impl Into<u64>>) {
+        let _ = values;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,14 @@
+struct Counter;
+
+#[pupactor::handlers]
+impl Counter {
+    async fn on_add(&mut self, value: u64) {
+        let _ = value;
+    }
+
+    async fn handle_add(&mut self, value: u64) {
+        let _ = value;
+    }
+}
+
+fn main() {}
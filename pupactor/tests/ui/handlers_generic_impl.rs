@@ -0,0 +1,10 @@
+struct Worker<S>(S);
+
+#[pupactor::handlers]
+impl<S> Worker<S> {
+    async fn on_put(&mut self, value: u64) {
+        let _ = value;
+    }
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+struct Counter;
+
+#[pupactor::handlers]
+impl Counter {
+    async fn on_add<T: Into<u64>>(&mut self, value: T) {
+        let _ = value.into();
+    }
+}
+
+fn main() {}
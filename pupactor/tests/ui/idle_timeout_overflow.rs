@@ -0,0 +1,7 @@
+use pupactor::Pupactor;
+
+#[derive(Pupactor)]
+#[actor(idle_timeout = "10000000000000000h")]
+struct MyActor {}
+
+fn main() {}
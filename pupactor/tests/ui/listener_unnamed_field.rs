@@ -0,0 +1,8 @@
+use pupactor::{Listener, Pupactor};
+use std::time::Instant;
+use tokio::time::Interval;
+
+#[derive(Pupactor)]
+struct MyActor(#[listener] Listener<Interval, Instant>);
+
+fn main() {}
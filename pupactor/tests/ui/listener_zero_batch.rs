@@ -0,0 +1,11 @@
+use pupactor::{Listener, Pupactor};
+use std::time::Instant;
+use tokio::time::Interval;
+
+#[derive(Pupactor)]
+struct MyActor {
+    #[listener(batch = 0)]
+    interval: Listener<Interval, Instant>,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use pupactor::ActorMsgHandle;
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "MyActor", client = "MyClient")]
+enum Value {
+    #[handle(ask)]
+    Count(u32),
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use pupactor::ActorMsgHandle;
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "MyActor")]
+enum Value {
+    #[handle(from)]
+    Pair(u32, u64),
+}
+
+fn main() {}
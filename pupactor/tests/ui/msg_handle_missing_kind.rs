@@ -0,0 +1,8 @@
+use pupactor::ActorMsgHandle;
+
+#[derive(ActorMsgHandle)]
+enum Value {
+    U32(u32),
+}
+
+fn main() {}
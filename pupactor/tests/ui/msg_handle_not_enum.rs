@@ -0,0 +1,7 @@
+use pupactor::ActorMsgHandle;
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "MyActor")]
+struct Value(u32);
+
+fn main() {}
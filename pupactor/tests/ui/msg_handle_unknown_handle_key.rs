@@ -0,0 +1,11 @@
+use pupactor::ActorMsgHandle;
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "MyActor")]
+enum Value {
+    U32(u32),
+    #[handle(method = "on_pair")]
+    Pair(u32, u64),
+}
+
+fn main() {}
@@ -0,0 +1,44 @@
+#![allow(refining_impl_trait)]
+
+use pupactor::{ActorMsgHandle, ActorShutdown, Pupactor};
+
+#[derive(ActorShutdown)]
+struct Stop;
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "MyActor", from)]
+enum Value {
+    U32(u32),
+    Reset,
+}
+
+#[derive(Pupactor)]
+#[actor(shutdown = "Stop", mailbox = "Value", idle_timeout = "1s")]
+struct MyActor {
+    #[listener(on_close = "notify")]
+    mailbox: pupactor::Mailbox<Value, Stop>,
+    #[listener(batch = 8)]
+    ticks: pupactor::Listener<tokio::time::Interval, std::time::Instant, Stop>,
+}
+
+impl pupactor::Handle<u32> for MyActor {
+    fn handle(&mut self, _: u32) {}
+}
+
+impl pupactor::Handle<ValueReset> for MyActor {
+    fn handle(&mut self, _: ValueReset) {}
+}
+
+impl pupactor::Handle<std::time::Instant> for MyActor {
+    fn handle(&mut self, _: std::time::Instant) {}
+}
+
+impl pupactor::Handle<pupactor::ListenerClosed> for MyActor {
+    fn handle(&mut self, _: pupactor::ListenerClosed) {}
+}
+
+impl pupactor::StopActor<Stop> for MyActor {
+    async fn stop_actor(self, _: Stop) {}
+}
+
+fn main() {}
@@ -0,0 +1,60 @@
+#![allow(refining_impl_trait)]
+
+use pupactor::{ActorMsgHandle, ActorShutdown, Break, Handle, Pupactor, StopActor};
+
+#[derive(ActorShutdown)]
+struct Shutdown;
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "First")]
+enum FirstMsg {
+    Stop,
+    Break,
+}
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "Second")]
+enum SecondMsg {
+    Stop,
+}
+
+#[derive(Pupactor)]
+#[actor(shutdown = "Shutdown", mailbox = "FirstMsg")]
+struct First {
+    #[listener]
+    mailbox: pupactor::Mailbox<FirstMsg, Shutdown>,
+}
+
+#[derive(Pupactor)]
+#[actor(shutdown = "Shutdown", mailbox = "SecondMsg")]
+struct Second {
+    #[listener]
+    mailbox: pupactor::Mailbox<SecondMsg, Shutdown>,
+}
+
+impl Handle<FirstMsgStop> for First {
+    fn handle(&mut self, _: FirstMsgStop) -> Break {
+        Break
+    }
+}
+
+impl Handle<FirstMsgBreak> for First {
+    fn handle(&mut self, _: FirstMsgBreak) -> Break {
+        Break
+    }
+}
+
+impl Handle<SecondMsgStop> for Second {
+    fn handle(&mut self, _: SecondMsgStop) -> Break {
+        Break
+    }
+}
+impl StopActor<Shutdown> for First {
+    async fn stop_actor(self, _: Shutdown) {}
+}
+
+impl StopActor<Shutdown> for Second {
+    async fn stop_actor(self, _: Shutdown) {}
+}
+
+fn main() {}
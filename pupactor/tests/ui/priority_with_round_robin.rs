@@ -0,0 +1,14 @@
+use pupactor::{Listener, Pupactor};
+use std::time::Instant;
+use tokio::time::Interval;
+
+#[derive(Pupactor)]
+#[actor(select = "round_robin")]
+struct MyActor {
+    #[listener(priority = 1)]
+    fast: Listener<Interval, Instant>,
+    #[listener]
+    slow: Listener<Interval, Instant>,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use pupactor::Pupactor;
+
+#[derive(Pupactor)]
+enum MyActor {}
+
+fn main() {}
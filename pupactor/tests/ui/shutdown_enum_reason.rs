@@ -0,0 +1,10 @@
+use pupactor::ActorShutdown;
+
+#[derive(ActorShutdown)]
+#[shutdown(reason = "stopped")]
+enum Shutdown {
+    Signal,
+    Fatal(String),
+}
+
+fn main() {}
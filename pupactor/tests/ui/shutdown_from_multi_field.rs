@@ -0,0 +1,10 @@
+use pupactor::ActorShutdown;
+
+#[derive(ActorShutdown)]
+enum Shutdown {
+    Signal,
+    #[shutdown(from)]
+    Fatal(String, u32),
+}
+
+fn main() {}
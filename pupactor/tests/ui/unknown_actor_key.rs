@@ -0,0 +1,7 @@
+use pupactor::Pupactor;
+
+#[derive(Pupactor)]
+#[actor(shutdwn = "MyShutdown")]
+struct MyActor {}
+
+fn main() {}
@@ -0,0 +1,43 @@
+use pupactor::{handlers, Break, Mailbox, Pupactor};
+
+
+#[derive(Pupactor)]
+struct Counter {
+    count: u64,
+    #[listener]
+    mailbox: Mailbox<CounterMsg>,
+}
+
+
+#[handlers]
+impl Counter {
+    /// Adds to the counter
+    async fn on_add(&mut self, value: u64) {
+        self.count += value;
+    }
+
+    #[ask]
+    async fn on_get(&mut self) -> u64 {
+        self.count
+    }
+
+    async fn on_stop(&mut self) -> Break {
+        Break
+    }
+}
+
+
+pub async fn counter_test() {
+    let (client, mailbox) = CounterClient::channel();
+    let actor = tokio::spawn(async move {
+        let mut counter = Counter { count: 0, mailbox };
+        let _ = pupactor::Actor::infinite_loop(&mut counter).await;
+        counter.count
+    });
+
+    client.add(2);
+    client.add(3);
+    println!("Counter: {:?}", client.get().await);
+    client.stop();
+    println!("Counter stopped at {:?}, ask after stop: {:?}", actor.await, client.get().await);
+}
@@ -1,24 +1,35 @@
-use pupactor::{run_actor, Actor, ActorCommand, ActorMsg, AsyncHandle, Break, Continue, Handle, InitActor, Kill, Listener, StopActor};
+use pupactor::{run_actor, ActorMsg, AsyncHandle, Break, Continue, Handle, InitActor, Kill, Listener, ListenerClosed, SignalKind, SignalListener, StopActor};
 use pupactor::{ActorMsgHandle, ActorShutdown, Pupactor};
-use std::convert::Infallible;
 use std::time::Instant;
-use tokio::select;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, oneshot};
 use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::time::Interval;
 
 
 #[derive(ActorMsgHandle)]
-#[actor(kind = "MyFirstTestActor")]
+#[actor(kind = "MyFirstTestActor", from, client = "MyFirstTestClient")]
 pub enum Value {
     U32(u32),
     U64(u64),
     String(String),
+    Reset,
+    // Only sent through `MyFirstTestClient`
+    #[allow(dead_code)]
+    Resize { width: u32, height: u32 },
+    #[allow(dead_code)]
+    #[handle(with = "on_log")]
+    Log(String, u8),
+    Count(oneshot::Sender<usize>),
 }
 
 
-#[derive(ActorShutdown)]
-pub struct MyActorShutdown;
+#[derive(ActorShutdown, Clone)]
+#[shutdown(display)]
+pub enum MyActorShutdown {
+    Terminated,
+    #[shutdown(from, reason = "too many ticks")]
+    TooManyTicks(usize),
+}
 
 
 // generated
@@ -40,10 +51,12 @@ struct MyFirstTestActor {
     some_other_data: usize,
     #[listener]
     interval: Listener<Interval, Instant>,
-    #[listener]
+    #[listener(handler = "on_slow_tick")]
     interval2: Listener<Interval, Instant>,
-    #[listener]
+    #[listener(on_close = "notify", batch = 16)]
     channel: Listener<UnboundedReceiver<ActorMsg<Instant>>, Instant>,
+    #[listener(priority = 10)]
+    terminate: Listener<SignalListener<Instant, MyActorShutdown>, Instant, MyActorShutdown>,
 }
 
 
@@ -55,6 +68,7 @@ impl InitActor<UnboundedReceiver<ActorMsg<Instant>>> for MyFirstTestActor {
             interval: Listener::new(tokio::time::interval(tokio::time::Duration::from_secs(1))),
             interval2: Listener::new(tokio::time::interval(tokio::time::Duration::from_secs(2))),
             channel: Listener::new(receiver),
+            terminate: Listener::new(SignalListener::shutdown(SignalKind::terminate(), MyActorShutdown::Terminated).expect("SIGTERM handler")),
         }
     }
 }
@@ -154,7 +168,7 @@ impl AsyncHandle<u32> for MyFirstTestActor {
 impl Handle<u64> for MyFirstTestActor {
     fn handle(&mut self, value: u64) -> Kill<MyActorShutdown> {
         let _ = value;
-        Kill(MyActorShutdown)
+        Kill(MyActorShutdown::Terminated)
     }
 }
 
@@ -162,18 +176,40 @@ impl Handle<u64> for MyFirstTestActor {
 impl AsyncHandle<String> for MyFirstTestActor {
     async fn async_handle(&mut self, value: String) -> Option<Break> {
         let _ = value;
-        None
+        (!self.some_data).then_some(Break)
+    }
+}
+
+
+impl Handle<ValueReset> for MyFirstTestActor {
+    fn handle(&mut self, _: ValueReset) {
+        self.some_other_data = 0;
+    }
+}
+
+
+impl Handle<(u32, u32)> for MyFirstTestActor {
+    fn handle(&mut self, (width, height): (u32, u32)) {
+        println!("Resize to {}x{}", width, height);
+    }
+}
+
+
+impl Handle<oneshot::Sender<usize>> for MyFirstTestActor {
+    fn handle(&mut self, reply: oneshot::Sender<usize>) {
+        let _ = reply.send(self.some_other_data);
     }
 }
 
 
 impl AsyncHandle<Instant> for MyFirstTestActor {
-    async fn async_handle(&mut self, value: Instant) -> Option<Kill<MyActorShutdown>> {
+    async fn async_handle(&mut self, value: Instant) -> Option<Kill<usize>> {
+        let _ = value;
         self.some_other_data += 1;
         println!("New msg, couner: {}", self.some_other_data);
 
         if self.some_other_data > 5 {
-            Some(Kill(MyActorShutdown))
+            Some(Kill(self.some_other_data))
         } else {
             None
         }
@@ -181,10 +217,31 @@ impl AsyncHandle<Instant> for MyFirstTestActor {
 }
 
 
+impl MyFirstTestActor {
+    async fn on_log(&mut self, line: String, level: u8) -> Continue {
+        println!("Log [{}]: {}", level, line);
+    }
+
+    async fn on_slow_tick(&mut self, value: Instant) -> Continue {
+        let _ = value;
+        println!("Slow tick, couner: {}", self.some_other_data);
+    }
+}
+
+
+impl AsyncHandle<ListenerClosed> for MyFirstTestActor {
+    async fn async_handle(&mut self, closed: ListenerClosed) -> Continue {
+        println!("Listener closed: {}", closed.listener);
+    }
+}
+
+
 impl StopActor<MyActorShutdown> for MyFirstTestActor {
     async fn stop_actor(self, shut_down: MyActorShutdown) {
-        println!("Called Shutdown");
-        let _ = shut_down;
+        match shut_down {
+            MyActorShutdown::TooManyTicks(ticks) => println!("Called Shutdown: {} ({})", shut_down, ticks),
+            MyActorShutdown::Terminated => println!("Called Shutdown: {}", shut_down),
+        }
     }
 }
 
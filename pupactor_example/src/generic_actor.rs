@@ -0,0 +1,57 @@
+use pupactor::{Actor, ActorRef, Handle, Mailbox};
+use pupactor::{ActorMsgHandle, Pupactor};
+use std::collections::HashMap;
+
+
+/// Backend the worker is parameterized over
+pub trait Storage: Send + Sync + 'static {
+    fn put(&mut self, key: String, value: u64);
+}
+
+
+impl Storage for HashMap<String, u64> {
+    fn put(&mut self, key: String, value: u64) {
+        self.insert(key, value);
+    }
+}
+
+
+#[derive(ActorMsgHandle)]
+#[actor(kind = "Worker<S>", generics = "<S: Storage>")]
+pub enum WorkerMsg<T> {
+    Put((String, u64)),
+    Custom(T),
+}
+
+
+#[derive(Pupactor)]
+#[actor(mailbox = "WorkerMsg<u64>", idle_timeout = "100ms")]
+pub struct Worker<S: Storage> {
+    storage: S,
+    #[listener]
+    mailbox: Mailbox<WorkerMsg<u64>>,
+}
+
+
+impl<S: Storage> Handle<(String, u64)> for Worker<S> {
+    fn handle(&mut self, (key, value): (String, u64)) {
+        self.storage.put(key, value);
+    }
+}
+
+
+impl<S: Storage> Handle<u64> for Worker<S> {
+    fn handle(&mut self, value: u64) {
+        self.storage.put(value.to_string(), value);
+    }
+}
+
+
+pub async fn generic_test() {
+    let (actor_ref, mailbox): (ActorRef<WorkerMsg<u64>>, _) = Worker::<HashMap<String, u64>>::mailbox();
+    actor_ref.send(WorkerMsg::Put(("a".to_string(), 1)));
+    actor_ref.send(WorkerMsg::Custom(2));
+    let mut worker = Worker { storage: HashMap::new(), mailbox };
+    let _ = worker.infinite_loop().await;
+    println!("generic worker stored {:?}", worker.storage);
+}
@@ -3,11 +3,15 @@
 
 use crate::first_test_actor::test_function;
 
+mod counter_actor;
 mod first_test_actor;
+mod generic_actor;
 
 #[tokio::main]
 async fn main() {
     println!("Hello, world!");
+    generic_actor::generic_test().await;
+    counter_actor::counter_test().await;
     test_function().await;
     println!("Done");
 }
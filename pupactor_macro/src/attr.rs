@@ -0,0 +1,141 @@
+use proc_macro2::{Span, TokenStream};
+use std::str::FromStr;
+use syn::meta::ParseNestedMeta;
+use syn::parse::{Parse, Parser};
+use syn::{Attribute, Ident, Lit, LitStr, Meta, Token};
+
+/// Arguments of helper attributes: `key = "value"`, `key = 10` or a bare `key`
+///
+/// Every derive lists its known keys once, unknown keys are rejected while parsing.
+pub(crate) struct AttrArgs {
+    args: Vec<(Ident, Option<Lit>)>,
+}
+
+
+impl AttrArgs {
+    /// Collects the arguments of every `#[name(...)]` attribute
+    pub(crate) fn parse_all(attrs: &[Attribute], name: &str, keys: &[&str]) -> syn::Result<Self> {
+        let mut args = AttrArgs { args: Vec::new() };
+        for attr in attrs.iter().filter(|attr| attr.path().is_ident(name)) {
+            args.args.extend(Self::parse(attr, keys)?.args);
+        }
+        Ok(args)
+    }
+
+    pub(crate) fn parse(attr: &Attribute, keys: &[&str]) -> syn::Result<Self> {
+        let mut args = AttrArgs { args: Vec::new() };
+        // #[listener] без аргументов допустим
+        if matches!(attr.meta, Meta::Path(_)) {
+            return Ok(args);
+        }
+        attr.parse_nested_meta(|meta| args.push(meta, keys))?;
+        Ok(args)
+    }
+
+    /// Arguments of an attribute macro, `#[handlers(msg = "CounterMsg")]`
+    pub(crate) fn parse_args(tokens: TokenStream, keys: &[&str]) -> syn::Result<Self> {
+        let mut args = AttrArgs { args: Vec::new() };
+        syn::meta::parser(|meta| args.push(meta, keys)).parse2(tokens)?;
+        Ok(args)
+    }
+
+    fn push(&mut self, meta: ParseNestedMeta, keys: &[&str]) -> syn::Result<()> {
+        let key = match meta.path.get_ident() {
+            Some(key) if keys.iter().any(|known| key == known) => key.clone(),
+            _ => return Err(meta.error(format!("unknown attribute, expected one of: {}", keys.join(", ")))),
+        };
+        let value = if meta.input.peek(Token![=]) {
+            Some(meta.value()?.parse::<Lit>()?)
+        } else {
+            None
+        };
+        self.args.push((key, value));
+        Ok(())
+    }
+
+    fn get(&self, key: &str) -> Option<&(Ident, Option<Lit>)> {
+        self.args.iter().rev().find(|(ident, _)| ident == key)
+    }
+
+    /// Span of the value, or of the key for a bare `key`
+    pub(crate) fn span(&self, key: &str) -> Option<Span> {
+        self.get(key).map(|(ident, value)| value.as_ref().map_or(ident.span(), Lit::span))
+    }
+
+    pub(crate) fn str(&self, key: &str) -> syn::Result<Option<LitStr>> {
+        match self.get(key) {
+            None => Ok(None),
+            Some((_, Some(Lit::Str(lit_str)))) => Ok(Some(lit_str.clone())),
+            Some((ident, _)) => Err(syn::Error::new(ident.span(), format!("expected `{} = \"...\"`", key))),
+        }
+    }
+
+    pub(crate) fn int<N>(&self, key: &str) -> syn::Result<Option<N>>
+    where
+        N: FromStr,
+        N::Err: std::fmt::Display,
+    {
+        match self.get(key) {
+            None => Ok(None),
+            Some((_, Some(Lit::Int(lit_int)))) => Ok(Some(lit_int.base10_parse()?)),
+            Some((ident, _)) => Err(syn::Error::new(ident.span(), format!("expected `{} = <integer>`", key))),
+        }
+    }
+
+    /// Bare `key` or `key = true`
+    pub(crate) fn flag(&self, key: &str) -> syn::Result<bool> {
+        match self.get(key) {
+            None => Ok(false),
+            Some((_, None)) => Ok(true),
+            Some((_, Some(Lit::Bool(lit_bool)))) => Ok(lit_bool.value),
+            Some((ident, _)) => Err(syn::Error::new(ident.span(), format!("expected `{}` or `{} = false`", key, key))),
+        }
+    }
+
+    /// `Ident` from a string value, `kind = "MyActor"`
+    pub(crate) fn ident(&self, key: &str) -> syn::Result<Option<Ident>> {
+        Ok(self.str(key)?.map(|lit_str| Ident::new(&lit_str.value(), lit_str.span())))
+    }
+
+    /// Any syntax from a string value, `kind = "Worker<S>"`, `bound = "S: Storage"`
+    pub(crate) fn parse_str<T: Parse>(&self, key: &str) -> syn::Result<Option<T>> {
+        self.str(key)?.map(|lit_str| lit_str.parse()).transpose()
+    }
+
+    /// One of `choices` from a string value, `on_close = "disable"`
+    pub(crate) fn choice<T: Copy>(&self, key: &str, choices: &[(&str, T)]) -> syn::Result<Option<T>> {
+        let Some(lit_str) = self.str(key)? else {
+            return Ok(None);
+        };
+        let value = lit_str.value();
+        match choices.iter().find(|(name, _)| *name == value) {
+            Some((_, choice)) => Ok(Some(*choice)),
+            None => {
+                let names = choices.iter().map(|(name, _)| format!("`{}`", name)).collect::<Vec<_>>();
+                Err(syn::Error::new(lit_str.span(), format!("expected one of {}", names.join(", "))))
+            }
+        }
+    }
+
+    /// Duration in milliseconds: `"500ms"`, `"30s"`, `"5m"`, `"1h"`
+    pub(crate) fn duration_ms(&self, key: &str) -> syn::Result<Option<u64>> {
+        let Some(lit_str) = self.str(key)? else {
+            return Ok(None);
+        };
+        let value = lit_str.value();
+        let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+        let (amount, unit) = value.split_at(split);
+        let multiplier = match unit.trim() {
+            "ms" => 1,
+            "s" => 1_000,
+            "m" => 60_000,
+            "h" => 3_600_000,
+            _ => return Err(syn::Error::new(lit_str.span(), "expected a duration like \"500ms\", \"30s\", \"5m\" or \"1h\"")),
+        };
+        let amount: u64 = amount.parse()
+            .map_err(|_| syn::Error::new(lit_str.span(), "expected a duration like \"500ms\", \"30s\", \"5m\" or \"1h\""))?;
+        amount.checked_mul(multiplier)
+            .map(Some)
+            .ok_or_else(|| syn::Error::new(lit_str.span(), "duration does not fit into u64 milliseconds"))
+    }
+}
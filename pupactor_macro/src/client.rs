@@ -0,0 +1,127 @@
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Attribute, GenericArgument, Ident, PathArguments, Type, Visibility};
+
+/// Typed wrapper around `ActorRef<Msg, Shutdown>`, one method per message
+pub(crate) struct Client<'a> {
+    pub(crate) vis: &'a Visibility,
+    pub(crate) name: &'a Ident,
+    pub(crate) msg: TokenStream,
+    pub(crate) shutdown: TokenStream,
+    pub(crate) methods: Vec<ClientMethod>,
+}
+
+
+pub(crate) struct ClientMethod {
+    pub(crate) docs: Vec<Attribute>,
+    pub(crate) name: Ident,
+    pub(crate) args: Vec<(Ident, Type)>,
+    /// Reply type for `ask` methods, the reply sender is bound to `__reply` in `construct`
+    pub(crate) reply: Option<Type>,
+    /// Builds the message from `args`
+    pub(crate) construct: TokenStream,
+}
+
+
+impl Client<'_> {
+    pub(crate) fn expand(&self) -> TokenStream {
+        let Client { vis, name, msg, shutdown, .. } = self;
+        let methods = self.methods.iter().map(|method| {
+            let ClientMethod { docs, name, construct, .. } = method;
+            let arg_names = method.args.iter().map(|(arg, _)| arg);
+            let arg_types = method.args.iter().map(|(_, ty)| ty);
+            match &method.reply {
+                Some(reply) => quote! {
+                    #(#docs)*
+                    #vis async fn #name(&self, #(#arg_names: #arg_types),*) -> ::std::result::Result<#reply, ::pupactor::AskError> {
+                        self.actor_ref.ask_with(|__reply| #construct).await
+                    }
+                },
+                None => quote! {
+                    #(#docs)*
+                    #[inline]
+                    #vis fn #name(&self, #(#arg_names: #arg_types),*) {
+                        self.actor_ref.send(#construct);
+                    }
+                },
+            }
+        });
+
+        quote! {
+            #vis struct #name {
+                actor_ref: ::pupactor::ActorRef<#msg, #shutdown>,
+            }
+
+            impl ::std::clone::Clone for #name {
+                #[inline]
+                fn clone(&self) -> Self {
+                    Self { actor_ref: self.actor_ref.clone() }
+                }
+            }
+
+            impl ::std::convert::From<::pupactor::ActorRef<#msg, #shutdown>> for #name {
+                #[inline]
+                fn from(actor_ref: ::pupactor::ActorRef<#msg, #shutdown>) -> Self {
+                    Self { actor_ref }
+                }
+            }
+
+            impl #name {
+                /// Client with the mailbox listener for the actor
+                #[inline]
+                #vis fn channel() -> (Self, ::pupactor::Mailbox<#msg, #shutdown>) {
+                    let (actor_ref, mailbox) = ::pupactor::ActorRef::channel();
+                    (Self { actor_ref }, mailbox)
+                }
+
+                #[inline]
+                #vis fn actor_ref(&self) -> &::pupactor::ActorRef<#msg, #shutdown> {
+                    &self.actor_ref
+                }
+
+                #(#methods)*
+            }
+        }
+    }
+}
+
+
+/// `R` from `oneshot::Sender<R>`, or from any `Sender<R>` when the variant is marked `ask`
+pub(crate) fn reply_type(ty: &Type, ask: bool) -> Option<Type> {
+    let Type::Path(path) = ty else {
+        return None;
+    };
+    let segments = path.path.segments.iter().collect::<Vec<_>>();
+    let (sender, module) = match segments.as_slice() {
+        [.., module, sender] => (*sender, Some(*module)),
+        [sender] => (*sender, None),
+        [] => return None,
+    };
+    if sender.ident != "Sender" || !(ask || module.is_some_and(|module| module.ident == "oneshot")) {
+        return None;
+    }
+    match &sender.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first() {
+            Some(GenericArgument::Type(reply)) if args.args.len() == 1 => Some(reply.clone()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+
+/// `GetCount` -> `get_count`
+pub(crate) fn snake_case(camel: &str) -> String {
+    let mut snake = String::with_capacity(camel.len() + 4);
+    for (i, char) in camel.chars().enumerate() {
+        if char.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(char.to_lowercase());
+        } else {
+            snake.push(char);
+        }
+    }
+    snake
+}
@@ -0,0 +1,205 @@
+use crate::attr::AttrArgs;
+use crate::client::{Client, ClientMethod};
+use crate::collect_errors;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use std::collections::HashMap;
+use syn::{FnArg, GenericArgument, Ident, ImplItem, ImplItemFn, ItemImpl, Pat, PathArguments, ReturnType, Type, Visibility};
+
+/// `#[handlers(msg = "CounterMsg", client = "CounterClient", vis = "pub")]`
+pub(crate) fn expand(args: TokenStream, mut item: ItemImpl) -> syn::Result<TokenStream> {
+    let args = AttrArgs::parse_args(args, &["msg", "client", "vis"])?;
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&item.generics, "`handlers` does not support generic impl blocks"));
+    }
+    let actor_ty = item.self_ty.clone();
+    let actor_name = match actor_ty.as_ref() {
+        Type::Path(path) => path.path.segments.last().map(|segment| segment.ident.clone()),
+        _ => None,
+    }.ok_or_else(|| syn::Error::new_spanned(&actor_ty, "`handlers` expects an impl block for a named type"))?;
+
+    let msg_name = args.ident("msg")?.unwrap_or_else(|| format_ident!("{}Msg", actor_name));
+    let client_name = args.ident("client")?.unwrap_or_else(|| format_ident!("{}Client", actor_name));
+    let vis = args.parse_str::<Visibility>("vis")?.unwrap_or(Visibility::Inherited);
+
+    // Обработчики - async fn с &mut self, остальные методы блока не трогаем
+    let handlers = item.items.iter_mut().filter_map(|item| match item {
+        ImplItem::Fn(method) if is_handler(method) => Some(Handler::parse(method)),
+        _ => None,
+    }).collect::<Vec<_>>();
+    let handlers = collect_errors(handlers)?;
+    if handlers.is_empty() {
+        return Err(syn::Error::new_spanned(&item.self_ty, "`handlers` needs at least one `async fn(&mut self, ...)`"));
+    }
+
+    // Два метода на один вариант (`on_add` и `handle_add`) дали бы одинаковые варианты enum'а
+    let mut seen = HashMap::new();
+    let duplicates = handlers.iter().map(|handler| match seen.insert(handler.variant.to_string(), &handler.method) {
+        Some(first) => Err(syn::Error::new(
+            handler.method.span(),
+            format!("`{}` maps to the same variant `{}` as `{}`", handler.method, handler.variant, first),
+        )),
+        None => Ok(()),
+    }).collect::<Vec<_>>();
+    collect_errors(duplicates)?;
+
+    let variants = handlers.iter().map(|handler| {
+        let Handler { docs, variant, args, reply, .. } = handler;
+        let types = args.iter().map(|(_, ty)| ty);
+        let reply = reply.as_ref().map(|reply| quote! { ::pupactor::__private::tokio::sync::oneshot::Sender<#reply> });
+        let fields = types.map(|ty| quote! { #ty }).chain(reply);
+        quote! {
+            #(#docs)*
+            #variant(#(#fields),*),
+        }
+    });
+
+    let branches = handlers.iter().map(|handler| {
+        let Handler { method, variant, args, reply, .. } = handler;
+        let bindings = (0..args.len()).map(|i| format_ident!("__{}", i)).collect::<Vec<_>>();
+        match reply {
+            // Ответ уходит отправителю, актор продолжает работу
+            Some(_) => quote! {
+                #msg_name::#variant(#(#bindings,)* reply) => {
+                    let _ = reply.send(self.#method(#(#bindings),*).await);
+                    ::pupactor::ActorCommand::from(())
+                }
+            },
+            None => quote! {
+                #msg_name::#variant(#(#bindings),*) => self.#method(#(#bindings),*).await.into(),
+            },
+        }
+    });
+
+    let client = Client {
+        vis: &vis,
+        name: &client_name,
+        msg: quote! { #msg_name },
+        shutdown: quote! { <#actor_ty as ::pupactor::Actor>::ShutDown },
+        methods: handlers.iter().map(|handler| {
+            let variant = &handler.variant;
+            let arg_names = handler.args.iter().map(|(arg, _)| arg);
+            let reply = handler.reply.as_ref().map(|_| quote! { __reply });
+            ClientMethod {
+                docs: handler.docs.clone(),
+                name: handler.client_method.clone(),
+                args: handler.args.clone(),
+                reply: handler.reply.clone(),
+                construct: quote! { #msg_name::#variant(#(#arg_names,)* #reply) },
+            }
+        }).collect(),
+    }.expand();
+
+    Ok(quote! {
+        #item
+
+        #vis enum #msg_name {
+            #(#variants)*
+        }
+
+        impl ::pupactor::AsyncHandle<#msg_name> for #actor_ty {
+            #[inline(always)]
+            #[allow(refining_impl_trait)]
+            async fn async_handle(&mut self, value: #msg_name) -> ::pupactor::ActorCommand<Self::ShutDown> {
+                match value {
+                    #(#branches)*
+                }
+            }
+        }
+
+        #client
+    })
+}
+
+
+fn is_handler(method: &ImplItemFn) -> bool {
+    method.sig.asyncness.is_some() && matches!(
+        method.sig.inputs.first(),
+        Some(FnArg::Receiver(receiver)) if receiver.reference.is_some() && receiver.mutability.is_some()
+    )
+}
+
+
+struct Handler {
+    docs: Vec<syn::Attribute>,
+    method: Ident,
+    /// `on_add` -> `Add`
+    variant: Ident,
+    /// `on_add` -> `add`
+    client_method: Ident,
+    args: Vec<(Ident, Type)>,
+    /// `#[ask]` handlers reply with their return value
+    reply: Option<Type>,
+}
+
+
+impl Handler {
+    fn parse(method: &mut ImplItemFn) -> syn::Result<Self> {
+        if !method.sig.generics.params.is_empty() {
+            return Err(syn::Error::new_spanned(&method.sig.generics, "`handlers` does not support generic methods"));
+        }
+        let ask = method.attrs.iter().any(|attr| attr.path().is_ident("ask"));
+        method.attrs.retain(|attr| !attr.path().is_ident("ask"));
+
+        let name = method.sig.ident.to_string();
+        let short = name.strip_prefix("on_").or_else(|| name.strip_prefix("handle_")).unwrap_or(&name);
+        let args = method.sig.inputs.iter().skip(1).enumerate().filter_map(|(i, arg)| match arg {
+            FnArg::Typed(arg) => {
+                let name = match arg.pat.as_ref() {
+                    Pat::Ident(pat) => pat.ident.clone(),
+                    _ => format_ident!("arg{}", i),
+                };
+                // Аргументы становятся полями enum'а, который уходит в другую задачу
+                Some(match unsupported_arg(&arg.ty) {
+                    Some((ty, reason)) => Err(syn::Error::new_spanned(ty, format!("`handlers` arguments must be owned types, {}", reason))),
+                    None => Ok((name, (*arg.ty).clone())),
+                })
+            }
+            FnArg::Receiver(_) => None,
+        }).collect::<Vec<_>>();
+        let args = collect_errors(args)?;
+        let reply = ask.then(|| match &method.sig.output {
+            ReturnType::Type(_, ty) => (**ty).clone(),
+            ReturnType::Default => syn::parse_quote! { () },
+        });
+
+        Ok(Handler {
+            docs: method.attrs.iter().filter(|attr| attr.path().is_ident("doc")).cloned().collect(),
+            method: method.sig.ident.clone(),
+            variant: format_ident!("{}", upper_camel(short)),
+            client_method: format_ident!("{}", short),
+            args,
+            reply,
+        })
+    }
+}
+
+
+/// First reference or `impl Trait` inside an argument type
+fn unsupported_arg(ty: &Type) -> Option<(&Type, &'static str)> {
+    match ty {
+        Type::Reference(_) => Some((ty, "references are not supported")),
+        Type::ImplTrait(_) => Some((ty, "`impl Trait` is not supported")),
+        Type::Array(array) => unsupported_arg(&array.elem),
+        Type::Slice(slice) => unsupported_arg(&slice.elem),
+        Type::Group(group) => unsupported_arg(&group.elem),
+        Type::Paren(paren) => unsupported_arg(&paren.elem),
+        Type::Tuple(tuple) => tuple.elems.iter().find_map(unsupported_arg),
+        Type::Path(path) => path.path.segments.iter().find_map(|segment| match &segment.arguments {
+            PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+                GenericArgument::Type(ty) => unsupported_arg(ty),
+                _ => None,
+            }),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+
+fn upper_camel(snake: &str) -> String {
+    snake.split('_').filter(|part| !part.is_empty()).map(|part| {
+        let mut chars = part.chars();
+        chars.next().map(|first| first.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+    }).collect()
+}
@@ -1,111 +1,287 @@
 extern crate proc_macro;
+use crate::attr::AttrArgs;
+use crate::client::{reply_type, snake_case, Client, ClientMethod};
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Ident, LitStr};
+use proc_macro2::Span;
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parse_macro_input, parse_quote, Attribute, Data, DeriveInput, Fields, GenericParam, Generics, Ident, ItemImpl, LitStr, Token, Type, Variant, WherePredicate};
 
-#[proc_macro_derive(ActorMsgHandle, attributes(actor))]
+mod attr;
+mod client;
+mod handlers;
+
+/// Dispatches every variant of a message enum to the actor handlers
+///
+/// A single field goes to `AsyncHandle<Field>`, several fields as a tuple in declaration order,
+/// a unit variant as a generated marker struct named after the enum and the variant
+/// (`Value::Reset` is handled as `AsyncHandle<ValueReset>`).
+/// `#[handle(with = "method")]` on a variant calls the async method with the fields instead.
+///
+/// `#[actor(from)]` on the enum, or `#[handle(from)]` on a variant, generates `From<Field>`
+/// for single-field and unit variants, so `ActorRef::send` and `ActorRef::ask` take the inner value.
+///
+/// `#[actor(client = "MyActorClient")]` generates a typed client over `ActorRef`, one method per variant.
+/// A variant ending with `oneshot::Sender<R>` becomes `async fn(..) -> Result<R, AskError>`,
+/// the rest are sent without waiting. `#[handle(client = "name")]` renames the method.
+#[proc_macro_derive(ActorMsgHandle, attributes(actor, handle))]
 pub fn actor_msg_handle_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand_actor_msg_handle(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+
+fn expand_actor_msg_handle(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let enum_name = input.ident; // Имя enum'а
 
-    // Извлекаем имя структуры из атрибута #[actor(FirstTestActor)]
-    let mut actor_ident = None;
-    for attr in input.attrs {
-        if attr.path().is_ident("actor") {
-            attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("kind") {  // this parses the `kind`
-                    let value = meta.value()?;   // this parses the `=`
-                    let lit_str: LitStr = value.parse()?;  // this parses `"EarlGrey"`
-                    actor_ident = Some(Ident::new(&lit_str.value(), lit_str.span()));
-                    Ok(())
-                } else {
-                    Err(meta.error("no kind attribute"))
-                }
-            }).unwrap_or_else(|err| {
-                panic!("Failed to parse actor attribute: {}", err);
-            });
-        }
+    // Извлекаем тип актора из атрибута #[actor(kind = "FirstTestActor")]
+    let args = AttrArgs::parse_all(&input.attrs, "actor", &["kind", "generics", "bound", "from", "client"])?;
+    let actor_ty = args.parse_str::<Type>("kind")?.ok_or_else(|| syn::Error::new(
+        enum_name.span(),
+        "missing actor type, add `#[actor(kind = \"MyActor\")]`",
+    ))?;
+    // Параметры актора, которых нет у enum'а: #[actor(kind = "Worker<S>", generics = "<S: Storage>")]
+    let actor_generics = args.parse_str::<Generics>("generics")?;
+    let bounds = args.parse_str::<Bounds>("bound")?;
+    let from_all = args.flag("from")?;
+    let client_name = args.ident("client")?;
+    if client_name.is_some() && (!input.generics.params.is_empty() || actor_generics.is_some()) {
+        let span = args.span("client").unwrap_or(enum_name.span());
+        return Err(syn::Error::new(span, "`client` does not support generic messages or actors"));
     }
 
-    let actor_ident = actor_ident.expect("Expected actor name in #[actor(...)] attribute");
+    let (enum_impl_generics, enum_ty_generics, enum_where_clause) = input.generics.split_for_impl();
+    let enum_ty = quote! { #enum_name #enum_ty_generics };
+
+    let mut generics = input.generics.clone();
+    if let Some(actor_generics) = actor_generics {
+        generics.params.extend(actor_generics.params);
+        if let Some(where_clause) = actor_generics.where_clause {
+            generics.make_where_clause().predicates.extend(where_clause.predicates);
+        }
+    }
+    let generic_params = generic_param_idents(&generics);
 
     // Извлечение вариантов enum и генерация соответствующих match-веток
-    let variants = if let Data::Enum(data_enum) = input.data {
-        data_enum.variants.iter().map(|variant| {
-            let variant_name = &variant.ident;
-            match &variant.fields {
-                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
-                    // let field_type = &fields.unnamed[0].ty;
-                    quote! {
-                        #enum_name::#variant_name(val) => AsyncHandle::async_handle(self, val).await.into(),
+    let mut predicates = Vec::new();
+    let data_enum = match input.data {
+        Data::Enum(data_enum) => data_enum,
+        Data::Struct(data) => return Err(syn::Error::new(data.struct_token.span, "`ActorMsgHandle` can only be derived for enums")),
+        Data::Union(data) => return Err(syn::Error::new(data.union_token.span, "`ActorMsgHandle` can only be derived for enums")),
+    };
+    let mut markers = Vec::new();
+    let mut from_impls = Vec::new();
+    let mut client_methods = Vec::new();
+    let variants = data_enum.variants.iter().map(|variant| {
+        let variant_name = &variant.ident;
+        // Маркер с префиксом enum'а, чтобы одинаковые варианты разных enum'ов не конфликтовали
+        let marker = format_ident!("{}{}", enum_name, variant_name, span = variant_name.span());
+        let handle_args = AttrArgs::parse_all(&variant.attrs, "handle", &["with", "from", "client", "ask"])?;
+        let with = handle_args.ident("with")?;
+        let from = from_all || handle_args.flag("from")?;
+
+        // Поля варианта: паттерн для match и имена привязок
+        let (pattern, bindings, types) = match &variant.fields {
+            Fields::Unit => (quote! { #enum_name::#variant_name }, Vec::new(), Vec::new()),
+            Fields::Unnamed(fields) => {
+                let bindings = (0..fields.unnamed.len()).map(|i| format_ident!("__{}", i)).collect::<Vec<_>>();
+                let types = fields.unnamed.iter().map(|field| &field.ty).collect::<Vec<_>>();
+                (quote! { #enum_name::#variant_name(#(#bindings),*) }, bindings, types)
+            }
+            Fields::Named(fields) => {
+                let bindings = fields.named.iter().filter_map(|field| field.ident.clone()).collect::<Vec<_>>();
+                let types = fields.named.iter().map(|field| &field.ty).collect::<Vec<_>>();
+                (quote! { #enum_name::#variant_name { #(#bindings),* } }, bindings, types)
+            }
+        };
+
+        // From<Field> для одного поля, для маркерного типа - From<Marker>
+        if from {
+            let from = match (&variant.fields, types.as_slice()) {
+                (Fields::Unit, _) if with.is_none() => Some((quote! { #marker }, quote! { _ }, quote! { #enum_name::#variant_name })),
+                (Fields::Unnamed(_), [ty]) => Some((quote! { #ty }, quote! { value }, quote! { #enum_name::#variant_name(value) })),
+                (Fields::Named(_), [ty]) => {
+                    let field = &bindings[0];
+                    Some((quote! { #ty }, quote! { value }, quote! { #enum_name::#variant_name { #field: value } }))
+                }
+                _ => None,
+            };
+            match from {
+                Some((from_ty, value, construct)) => from_impls.push(quote! {
+                    impl #enum_impl_generics ::std::convert::From<#from_ty> for #enum_ty #enum_where_clause {
+                        #[inline(always)]
+                        fn from(#value: #from_ty) -> Self {
+                            #construct
+                        }
                     }
+                }),
+                // Enum-wide `from` skips variants without a single value
+                None if from_all => {}
+                None => {
+                    let span = handle_args.span("from").unwrap_or(variant_name.span());
+                    return Err(syn::Error::new(span, "`from` needs a single-field or a unit variant"));
                 }
-                _ => quote! {
-                    _ => panic!("Unsupported enum variant or structure"),
-                },
             }
-        }).collect::<Vec<_>>()
-    } else {
-        panic!("ActorMsgHandle can only be derived for enums");
-    };
+        }
+
+        if client_name.is_some() {
+            client_methods.push(client_method(variant, &enum_name, &handle_args)?);
+        }
+
+        // #[handle(with = "method")] - поля передаются в метод по порядку
+        if let Some(with) = with {
+            return Ok(quote! {
+                #pattern => self.#with(#(#bindings),*).await.into(),
+            });
+        }
+
+        // Без полей - маркерный тип `<Enum><Variant>`, одно поле - само значение, иначе кортеж
+        let (handled_ty, value) = match (bindings.as_slice(), types.as_slice()) {
+            ([], _) => {
+                let handled = (quote! { #marker }, quote! { #marker });
+                markers.push(marker);
+                handled
+            }
+            ([binding], [ty]) => (quote! { #ty }, quote! { #binding }),
+            _ => (quote! { (#(#types),*) }, quote! { (#(#bindings),*) }),
+        };
+        // Для обобщенных полей обработчик требуется явно
+        if types.iter().any(|ty| mentions_any(ty, &generic_params)) {
+            predicates.push(parse_quote! { #actor_ty: ::pupactor::AsyncHandle<#handled_ty> });
+        }
+        Ok(quote! {
+            #pattern => <Self as ::pupactor::AsyncHandle<#handled_ty>>::async_handle(self, #value).await.into(),
+        })
+    }).collect::<Vec<_>>();
+    let variants = collect_errors(variants)?;
+
+    // AsyncHandle<T> требует T: Send + 'static
+    for param in input.generics.type_params() {
+        let param = &param.ident;
+        predicates.push(parse_quote! { #param: ::std::marker::Send + 'static });
+    }
+    add_bounds(&mut generics, predicates.into_iter().chain(bounds.into_iter().flat_map(|bounds| bounds.0)));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    let vis = &input.vis;
+
+    let client = client_name.as_ref().map(|client_name| Client {
+        vis,
+        name: client_name,
+        msg: quote! { #enum_name },
+        shutdown: quote! { <#actor_ty as ::pupactor::Actor>::ShutDown },
+        methods: client_methods,
+    }.expand());
 
     // Генерация кода
-    let expanded = quote! {
-        impl AsyncHandle<#enum_name> for #actor_ident {
+    Ok(quote! {
+        #(
+            #[derive(Debug, Clone, Copy)]
+            #vis struct #markers;
+        )*
+
+        #(#from_impls)*
+
+        impl #impl_generics ::pupactor::AsyncHandle<#enum_ty> for #actor_ty #where_clause {
             #[inline(always)]
-            async fn async_handle(&mut self, value: #enum_name) -> ActorCommand<Self::ShutDown> {
+            #[allow(refining_impl_trait)]
+            async fn async_handle(&mut self, value: #enum_ty) -> ::pupactor::ActorCommand<Self::ShutDown> {
                 match value {
                     #(#variants)*
                 }
             }
         }
+
+        #client
+    })
+}
+
+
+/// Client method for a variant: a trailing `oneshot::Sender<R>` makes it an `ask`,
+/// the other fields become arguments
+fn client_method(variant: &Variant, enum_name: &Ident, handle_args: &AttrArgs) -> syn::Result<ClientMethod> {
+    let variant_name = &variant.ident;
+    let name = handle_args.ident("client")?.unwrap_or_else(|| format_ident!("{}", snake_case(&variant_name.to_string())));
+    let fields = variant.fields.iter().collect::<Vec<_>>();
+    let reply = match fields.last() {
+        Some(field) => reply_type(&field.ty, handle_args.flag("ask")?),
+        None => None,
+    };
+    if reply.is_none() && handle_args.flag("ask")? {
+        let span = handle_args.span("ask").unwrap_or(variant_name.span());
+        return Err(syn::Error::new(span, "`ask` needs a `Sender<R>` as the last field"));
+    }
+    let arg_fields = &fields[..fields.len() - reply.is_some() as usize];
+    let args = arg_fields.iter().enumerate().map(|(i, field)| {
+        let name = match &field.ident {
+            Some(ident) => ident.clone(),
+            None if arg_fields.len() == 1 => format_ident!("value"),
+            None => format_ident!("arg{}", i),
+        };
+        (name, field.ty.clone())
+    }).collect::<Vec<_>>();
+
+    let arg_names = args.iter().map(|(name, _)| name);
+    let reply_value = reply.as_ref().map(|_| quote! { __reply });
+    let construct = match &variant.fields {
+        Fields::Unit => quote! { #enum_name::#variant_name },
+        Fields::Unnamed(_) => quote! { #enum_name::#variant_name(#(#arg_names,)* #reply_value) },
+        Fields::Named(_) => {
+            let reply_field = reply.as_ref().and_then(|_| fields.last()?.ident.as_ref()).map(|field| quote! { #field: __reply });
+            quote! { #enum_name::#variant_name { #(#arg_names,)* #reply_field } }
+        }
     };
 
-    TokenStream::from(expanded)
+    Ok(ClientMethod {
+        docs: variant.attrs.iter().filter(|attr| attr.path().is_ident("doc")).cloned().collect(),
+        name,
+        args,
+        reply,
+        construct,
+    })
 }
 
 
 #[proc_macro_derive(Pupactor, attributes(actor, listener))]
 pub fn pupactor_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand_pupactor(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+
+fn expand_pupactor(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let struct_name = input.ident;
 
-    // Найдем атрибут actor, чтобы получить тип ShutDown
-    let mut shutdown_ident = None;
-    for attr in input.attrs {
-        if attr.path().is_ident("actor") {
-            attr.parse_nested_meta(|meta| {
-                if meta.path.is_ident("shutdown") {  // this parses the `kind`
-                    let value = meta.value()?;   // this parses the `=`
-                    let lit_str: LitStr = value.parse()?;  // this parses `"EarlGrey"`
-                    shutdown_ident = Some(Ident::new(&lit_str.value(), lit_str.span()));
-                    Ok(())
-                } else {
-                    Err(meta.error("no kind attribute"))
-                }
-            }).unwrap_or_else(|err| {
-                panic!("Failed to parse actor attribute: {}", err);
-            });
-        }
-    }
-    let shutdown_ident = shutdown_ident.expect("Expected an `actor(shutdown = \"...\")` attribute");
+    // Все ключи атрибута #[actor(...)] разбираются одинаково
+    let actor = ActorArgs::parse(&input.attrs)?;
+    let ActorArgs { shutdown, select_mode, select_span, name, mailbox, idle_timeout_ms, bounds } = actor;
 
+    // Параметры и where-clause структуры переносятся во все impl'ы
+    let mut generics = input.generics.clone();
+    add_bounds(&mut generics, bounds.into_iter().flat_map(|bounds| bounds.0));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    // todo
-    // let shutdown_ident = shutdown_ident.unwrap_or_else(|| {
-    //     let lit_str: LitStr = "Infallible".parse().unwrap();  // this parses `"EarlGrey"`
-    //     shutdown_ident = Some(Ident::new(&lit_str.value(), lit_str.span()));
-    // });
+    // Без `shutdown` актор останавливается только через Break
+    let shutdown = match shutdown {
+        Some(shutdown) => quote! { #shutdown },
+        None => quote! { ::std::convert::Infallible },
+    };
 
     // Находим все поля с атрибутом #[listener]
-    let listeners = if let Data::Struct(data_struct) = input.data {
-        data_struct.fields.iter().filter_map(|field| {
-            let field_name = field.ident.clone();
-            field.attrs.iter().find(|attr| attr.path().is_ident("listener")).map(|_| field_name)
-        }).collect::<Vec<_>>()
-    } else {
-        panic!("`Pupactor` can only be derived for structs");
+    let data_struct = match input.data {
+        Data::Struct(data_struct) => data_struct,
+        Data::Enum(data) => return Err(syn::Error::new(data.enum_token.span, "`Pupactor` can only be derived for structs")),
+        Data::Union(data) => return Err(syn::Error::new(data.union_token.span, "`Pupactor` can only be derived for structs")),
     };
+    let listeners = data_struct.fields.iter().filter_map(|field| {
+        let attr = field.attrs.iter().find(|attr| attr.path().is_ident("listener"))?;
+        Some(match &field.ident {
+            Some(field_name) => ListenerField::parse(field_name.clone(), attr),
+            None => Err(syn::Error::new_spanned(field, "`Pupactor` listeners must be named fields")),
+        })
+    }).collect::<Vec<_>>();
+    let listeners = collect_errors(listeners)?;
 
 
     /*
@@ -130,64 +306,474 @@ pub fn pupactor_derive(input: TokenStream) -> TokenStream {
      */
 
 
-    // Генерация кода для каждого listener
-    let listener_branches = listeners.iter().map(|field_name| {
+    // Приоритет задан хотя бы для одного listener'а - опрос становится biased
+    let has_priority = listeners.iter().any(|listener| listener.priority.is_some());
+    let select_mode = match select_mode {
+        Some(select_mode @ (SelectMode::Random | SelectMode::RoundRobin)) if has_priority => {
+            let message = format!("`priority` can not be combined with `select = \"{}\"`", select_mode.name());
+            return Err(syn::Error::new(select_span.unwrap_or(struct_name.span()), message));
+        }
+        Some(select_mode) => select_mode,
+        None if has_priority => SelectMode::Biased,
+        None => SelectMode::Random,
+    };
+    // С одним listener'ом чередовать нечего
+    let select_mode = if listeners.len() < 2 { SelectMode::Biased } else { select_mode };
+    let mut listeners = listeners;
+    if let SelectMode::Biased = select_mode {
+        // Стабильная сортировка: при равном приоритете порядок объявления
+        listeners.sort_by_key(|listener| std::cmp::Reverse(listener.priority.unwrap_or(0)));
+    }
+
+    // Listener'ы опрашивает общий цикл, сгенерированный код только опрашивает и обрабатывает по индексу
+    let indexes = (0..listeners.len()).collect::<Vec<_>>();
+    let polls = listeners.iter().map(|listener| {
+        let field_name = &listener.field_name;
+        let limit = listener.batch.unwrap_or(1);
         quote! {
-            msg = Listener::next_msg(&mut self.#field_name) => {
-                if let Some(msg) = msg {
-                    match msg {
-                        ActorMsg::Msg(msg) => {
-                            let command: ActorCommand<Self::ShutDown> = <Self as AsyncHandle<_>>::async_handle(self, msg).await.into();
-                            if let Err(err) = command.0 {
-                                let _ = err?;
-                                break;
-                            } else {
-                                continue;
-                            }
-                        }
-                        ActorMsg::Shutdown(shutdown) => {
-                            return Err(Self::ShutDown::from(shutdown));
-                        }
-                    }
-                } else {
-                    break;
-                }
+            ::pupactor::__private::LoopSource::poll_pending(&mut self.#field_name, cx, #limit)
+        }
+    });
+    let handles = listeners.iter().map(ListenerField::handle_branch);
+    let on_close = listeners.iter().map(|listener| listener.on_close.path());
+    let select = select_mode.path();
+
+    // ListenerClosed только для on_close = "notify"
+    let notify = listeners.iter().enumerate()
+        .filter(|(_, listener)| matches!(listener.on_close, OnClose::Notify))
+        .map(|(index, listener)| (index, listener.field_name.to_string()))
+        .collect::<Vec<_>>();
+    let closed = (!notify.is_empty()).then(|| {
+        let (notify_indexes, notify_names): (Vec<_>, Vec<_>) = notify.into_iter().unzip();
+        quote! {
+            async fn closed(&mut self, index: usize) -> ::pupactor::ActorCommand<#shutdown> {
+                let listener = match index {
+                    #(#notify_indexes => #notify_names,)*
+                    _ => return ::pupactor::ActorCommand::from(()),
+                };
+                let closed = ::pupactor::ListenerClosed { listener };
+                ::pupactor::__private::MessageSpan::new(&closed)
+                    .handle(async { <Self as ::pupactor::AsyncHandle<::pupactor::ListenerClosed>>::async_handle(self, closed).await.into() }).await
+            }
+        }
+    });
+
+    let name = name.map(|name| quote! {
+        #[inline(always)]
+        fn name() -> &'static str {
+            #name
+        }
+    });
+
+    let mailbox = mailbox.map(|mailbox| quote! {
+        impl #impl_generics #struct_name #ty_generics #where_clause {
+            /// `ActorRef` to this actor and the listener for its mailbox field
+            #[inline]
+            pub fn mailbox() -> (::pupactor::ActorRef<#mailbox, #shutdown>, ::pupactor::Mailbox<#mailbox, #shutdown>) {
+                ::pupactor::ActorRef::channel()
             }
         }
     });
 
+    let idle_timeout = match idle_timeout_ms {
+        Some(idle_timeout_ms) => quote! { ::std::option::Option::Some(::std::time::Duration::from_millis(#idle_timeout_ms)) },
+        None => quote! { ::std::option::Option::None },
+    };
+
     // Генерация полного кода
-    let expanded = quote! {
-        impl Actor for #struct_name {
-            type ShutDown = #shutdown_ident;
-
-            async fn infinite_loop(&mut self) -> Result<Break, Self::ShutDown> {
-                loop {
-                    select! {
-                        #(#listener_branches)*
+    Ok(quote! {
+        impl #impl_generics ::pupactor::Actor for #struct_name #ty_generics #where_clause {
+            type ShutDown = #shutdown;
+            #name
+
+            async fn infinite_loop(&mut self) -> ::std::result::Result<::pupactor::Break, Self::ShutDown> {
+                let config = ::pupactor::__private::LoopConfig {
+                    select: #select,
+                    on_close: &[#(#on_close),*],
+                    idle_timeout: #idle_timeout,
+                };
+                ::pupactor::__private::run_loop(self, &config).await
+            }
+        }
+
+        impl #impl_generics ::pupactor::__private::LoopListeners<#shutdown> for #struct_name #ty_generics #where_clause {
+            fn poll_listener(&mut self, index: usize, cx: &mut ::std::task::Context<'_>) -> ::pupactor::__private::ListenerPoll {
+                match index {
+                    #(#indexes => #polls,)*
+                    _ => ::std::unreachable!(),
+                }
+            }
+
+            async fn handle(&mut self, index: usize) -> ::pupactor::ActorCommand<#shutdown> {
+                match index {
+                    #(#indexes => #handles)*
+                    _ => ::std::unreachable!(),
+                }
+            }
+
+            #closed
+        }
+
+        #mailbox
+    })
+}
+
+/// `#[actor(...)]` on a `Pupactor` struct
+struct ActorArgs {
+    /// `shutdown = "MyShutdown"`, `Infallible` if missing
+    shutdown: Option<Type>,
+    /// `select = "random" | "biased" | "round_robin"`
+    select_mode: Option<SelectMode>,
+    select_span: Option<Span>,
+    /// `name = "..."`, overrides `Actor::name`
+    name: Option<LitStr>,
+    /// `mailbox = "Msg"`, generates `fn mailbox()` with the `ActorRef` and its listener
+    mailbox: Option<Type>,
+    /// `idle_timeout = "30s"`, stops the actor with `Break` when no listener fires in time
+    idle_timeout_ms: Option<u64>,
+    /// `bound = "S::Source: PollListener<Msg>"`, extra where-clause for the generated impls
+    bounds: Option<Bounds>,
+}
+
+
+impl ActorArgs {
+    fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let args = AttrArgs::parse_all(attrs, "actor", &["shutdown", "select", "name", "mailbox", "idle_timeout", "bound"])?;
+        Ok(ActorArgs {
+            shutdown: args.parse_str("shutdown")?,
+            select_mode: args.choice("select", &[
+                ("random", SelectMode::Random),
+                ("biased", SelectMode::Biased),
+                ("round_robin", SelectMode::RoundRobin),
+            ])?,
+            select_span: args.span("select"),
+            name: args.str("name")?,
+            mailbox: args.parse_str("mailbox")?,
+            idle_timeout_ms: args.duration_ms("idle_timeout")?,
+            bounds: args.parse_str("bound")?,
+        })
+    }
+}
+
+
+/// `bound = "S: Storage, S::Item: Send"`
+struct Bounds(Punctuated<WherePredicate, Token![,]>);
+
+
+impl Parse for Bounds {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        Punctuated::parse_terminated(input).map(Bounds)
+    }
+}
+
+
+/// All errors at once, so every bad variant or listener is reported in one build
+fn collect_errors<T>(results: Vec<syn::Result<T>>) -> syn::Result<Vec<T>> {
+    let mut values = Vec::with_capacity(results.len());
+    let mut error: Option<syn::Error> = None;
+    for result in results {
+        match (result, &mut error) {
+            (Ok(value), _) => values.push(value),
+            (Err(err), Some(error)) => error.combine(err),
+            (Err(err), error) => *error = Some(err),
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(values),
+    }
+}
+
+
+fn add_bounds(generics: &mut Generics, bounds: impl IntoIterator<Item=WherePredicate>) {
+    let mut bounds = bounds.into_iter().peekable();
+    if bounds.peek().is_some() {
+        generics.make_where_clause().predicates.extend(bounds);
+    }
+}
+
+
+/// Type and const parameters, lifetimes can not make a field type generic over the actor
+fn generic_param_idents(generics: &Generics) -> Vec<Ident> {
+    generics.params.iter().filter_map(|param| match param {
+        GenericParam::Type(param) => Some(param.ident.clone()),
+        GenericParam::Const(param) => Some(param.ident.clone()),
+        GenericParam::Lifetime(_) => None,
+    }).collect()
+}
+
+
+fn mentions_any(ty: &Type, idents: &[Ident]) -> bool {
+    fn walk(tokens: proc_macro2::TokenStream, idents: &[Ident]) -> bool {
+        tokens.into_iter().any(|token| match token {
+            proc_macro2::TokenTree::Ident(ident) => idents.contains(&ident),
+            proc_macro2::TokenTree::Group(group) => walk(group.stream(), idents),
+            _ => false,
+        })
+    }
+    walk(quote! { #ty }, idents)
+}
+
+
+/// Order in which the generated loop polls listeners
+#[derive(Clone, Copy)]
+enum SelectMode {
+    /// Start from a random listener on every iteration, like `tokio::select!`, default
+    Random,
+    /// Declaration order, or `#[listener(priority = N)]` order, highest first
+    Biased,
+    /// Start from the next listener on every iteration, so a busy source can not starve the others
+    RoundRobin,
+}
+
+
+impl SelectMode {
+    fn path(self) -> proc_macro2::TokenStream {
+        match self {
+            SelectMode::Random => quote! { ::pupactor::SelectMode::Random },
+            SelectMode::Biased => quote! { ::pupactor::SelectMode::Biased },
+            SelectMode::RoundRobin => quote! { ::pupactor::SelectMode::RoundRobin },
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            SelectMode::Random => "random",
+            SelectMode::Biased => "biased",
+            SelectMode::RoundRobin => "round_robin",
+        }
+    }
+}
+
+
+/// What happens when a listener returns `None`
+#[derive(Clone, Copy)]
+enum OnClose {
+    /// Stop the actor with `Ok(Break)`, default
+    Break,
+    /// Stop polling this listener, keep the actor running
+    Disable,
+    /// Stop polling this listener and handle `ListenerClosed`
+    Notify,
+}
+
+
+impl OnClose {
+    fn path(self) -> proc_macro2::TokenStream {
+        match self {
+            OnClose::Break => quote! { ::pupactor::OnClose::Break },
+            OnClose::Disable => quote! { ::pupactor::OnClose::Disable },
+            OnClose::Notify => quote! { ::pupactor::OnClose::Notify },
+        }
+    }
+}
+
+
+struct ListenerField {
+    field_name: Ident,
+    on_close: OnClose,
+    /// `#[listener(handler = "method")]`, otherwise `AsyncHandle<Msg>`
+    handler: Option<Ident>,
+    /// `#[listener(priority = N)]`, higher is polled first
+    priority: Option<i64>,
+    /// `#[listener(batch = N)]`, up to N queued messages go to `HandleBatch<Msg>` or to `AsyncHandle<Msg>` one by one
+    batch: Option<usize>,
+}
+
+
+impl ListenerField {
+    fn parse(field_name: Ident, attr: &Attribute) -> syn::Result<Self> {
+        let args = AttrArgs::parse(attr, &["on_close", "handler", "priority", "batch"])?;
+        let on_close = args.choice("on_close", &[
+            ("break", OnClose::Break),
+            ("disable", OnClose::Disable),
+            ("notify", OnClose::Notify),
+        ])?;
+        let batch = match args.int::<usize>("batch")? {
+            Some(0) => return Err(syn::Error::new(args.span("batch").unwrap_or(attr.pound_token.span), "batch size must be greater than 0")),
+            batch => batch,
+        };
+        Ok(ListenerField {
+            field_name,
+            on_close: on_close.unwrap_or(OnClose::Break),
+            handler: args.ident("handler")?,
+            priority: args.int("priority")?,
+            batch,
+        })
+    }
+
+    fn handle_call(&self) -> proc_macro2::TokenStream {
+        match (&self.handler, self.batch) {
+            (Some(handler), _) => quote! { self.#handler(msg) },
+            // HandleBatch, если актор его реализует, иначе AsyncHandle на каждое сообщение
+            (None, Some(_)) => quote! {
+                async {
+                    use ::pupactor::__private::{ViaAsyncHandle as _, ViaHandleBatch as _};
+                    let dispatch = ::pupactor::__private::BatchDispatch::<Self, _>::of(&msg);
+                    (&dispatch).dispatch(self, msg).await
+                }
+            },
+            (None, None) => quote! { <Self as ::pupactor::AsyncHandle<_>>::async_handle(self, msg) },
+        }
+    }
+
+    /// Handles the messages `poll_pending` left in the listener
+    fn handle_branch(&self) -> proc_macro2::TokenStream {
+        let field_name = &self.field_name;
+        let handle = self.handle_call();
+        if self.batch.is_none() {
+            return quote! {
+                match ::std::vec::Vec::pop(::pupactor::__private::LoopSource::pending(&mut self.#field_name)) {
+                    ::std::option::Option::Some(::pupactor::ActorMsg::Msg(msg)) => ::pupactor::__private::MessageSpan::new(&msg)
+                        .handle(async { #handle.await.into() }).await,
+                    ::std::option::Option::Some(::pupactor::ActorMsg::Shutdown(shutdown)) => {
+                        ::pupactor::ActorCommand(::std::result::Result::Err(::std::result::Result::Err(::std::convert::From::from(shutdown))))
+                    }
+                    ::std::option::Option::None => ::pupactor::ActorCommand::from(()),
+                }
+            };
+        }
+        quote! {
+            {
+                // Буфер вынимается на время обработки и возвращается с сохраненной памятью
+                let mut buffer = ::std::mem::take(::pupactor::__private::LoopSource::pending(&mut self.#field_name));
+                let (command, shutdown) = {
+                    let (msg, shutdown) = ::pupactor::__private::split_batch(&mut buffer);
+                    let command = if ::std::iter::ExactSizeIterator::len(&msg) > 0 {
+                        ::pupactor::__private::MessageSpan::batch(&msg).handle(async { #handle.await.into() }).await
+                    } else {
+                        ::pupactor::ActorCommand::from(())
+                    };
+                    (command, shutdown)
+                };
+                *::pupactor::__private::LoopSource::pending(&mut self.#field_name) = buffer;
+                match shutdown {
+                    ::std::option::Option::Some(shutdown) if command.0.is_ok() => {
+                        ::pupactor::ActorCommand(::std::result::Result::Err(::std::result::Result::Err(::std::convert::From::from(shutdown))))
                     }
+                    _ => command,
                 }
-                Ok(Break)
             }
         }
-    };
-    TokenStream::from(expanded)
+    }
+}
+
+
+/// Generates the message enum, its `AsyncHandle` dispatch and a typed client from an impl block
+///
+/// Every `async fn(&mut self, ...)` becomes a variant: `on_add(&mut self, a: u32)` is `Msg::Add(u32)`
+/// and `Client::add(a)`. Handlers marked `#[ask]` reply with their return value through
+/// `oneshot::Sender`, the client awaits it as `Result<R, AskError>`.
+/// The rest return anything `Into<ActorCommand>` and are sent without waiting.
+/// Handlers can't be generic or take references and `impl Trait`, every argument becomes an enum field.
+///
+/// `#[handlers(msg = "CounterMsg", client = "CounterClient", vis = "pub")]`,
+/// by default `{Actor}Msg` and `{Actor}Client`, private to the module.
+#[proc_macro_attribute]
+pub fn handlers(args: TokenStream, input: TokenStream) -> TokenStream {
+    let item = parse_macro_input!(input as ItemImpl);
+    handlers::expand(args.into(), item).unwrap_or_else(syn::Error::into_compile_error).into()
 }
 
+
 /// ActorShutdown msg required always implement `From<Infallible>`
-#[proc_macro_derive(ActorShutdown)]
+///
+/// Also implements `ShutdownReason` for logs: the snake case name of the struct or of the variant,
+/// `#[shutdown(reason = "...")]` on the struct or on the variant overrides it.
+/// On enums `#[shutdown(from)]` on a single-field variant generates `From<Field>`, so `Kill(err)`
+/// converts `err` into the shutdown. `#[shutdown(display)]` on the type implements `Display` with the reason.
+#[proc_macro_derive(ActorShutdown, attributes(shutdown))]
 pub fn actor_shutdown_derive(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
+    expand_actor_shutdown(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+
+fn expand_actor_shutdown(input: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
     let struct_name = input.ident; // Имя структуры
+    let args = AttrArgs::parse_all(&input.attrs, "shutdown", &["reason", "display"])?;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
-    let expanded = quote! {
-        impl From<Infallible> for #struct_name {
-            #[inline(always)]
-            fn from(_: Infallible) -> Self {
-                unreachable!()
+    let mut from_impls = Vec::new();
+    let reason = match input.data {
+        Data::Enum(data_enum) => {
+            if let Some(span) = args.span("reason") {
+                return Err(syn::Error::new(span, "`reason` on an enum is not supported, put `#[shutdown(reason = \"...\")]` on the variants"));
             }
+            let branches = data_enum.variants.iter().map(|variant| {
+                let variant_name = &variant.ident;
+                let variant_args = AttrArgs::parse_all(&variant.attrs, "shutdown", &["reason", "from"])?;
+                let reason = variant_args.str("reason")?.map(|reason| reason.value())
+                    .unwrap_or_else(|| snake_case(&variant_name.to_string()));
+
+                if variant_args.flag("from")? {
+                    let (ty, construct) = match &variant.fields {
+                        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                            (&fields.unnamed[0].ty, quote! { #struct_name::#variant_name(value) })
+                        }
+                        Fields::Named(fields) if fields.named.len() == 1 => {
+                            let field = &fields.named[0].ident;
+                            (&fields.named[0].ty, quote! { #struct_name::#variant_name { #field: value } })
+                        }
+                        _ => {
+                            let span = variant_args.span("from").unwrap_or(variant_name.span());
+                            return Err(syn::Error::new(span, "`from` needs a single-field variant"));
+                        }
+                    };
+                    from_impls.push(quote! {
+                        impl #impl_generics ::std::convert::From<#ty> for #struct_name #ty_generics #where_clause {
+                            #[inline(always)]
+                            fn from(value: #ty) -> Self {
+                                #construct
+                            }
+                        }
+                    });
+                }
+
+                Ok(quote! {
+                    #struct_name::#variant_name { .. } => #reason,
+                })
+            }).collect::<Vec<_>>();
+            let branches = collect_errors(branches)?;
+            quote! {
+                match *self {
+                    #(#branches)*
+                }
+            }
+        }
+        Data::Struct(_) => {
+            let reason = args.str("reason")?.map(|reason| reason.value())
+                .unwrap_or_else(|| snake_case(&struct_name.to_string()));
+            quote! { #reason }
         }
+        Data::Union(data) => return Err(syn::Error::new(data.union_token.span, "`ActorShutdown` can only be derived for structs and enums")),
     };
 
-    TokenStream::from(expanded)
+    let display = args.flag("display")?.then(|| quote! {
+        impl #impl_generics ::std::fmt::Display for #struct_name #ty_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(&::pupactor::ShutdownReason::reason(self))
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics ::std::convert::From<::std::convert::Infallible> for #struct_name #ty_generics #where_clause {
+            #[inline(always)]
+            fn from(_: ::std::convert::Infallible) -> Self {
+                ::std::unreachable!()
+            }
+        }
+
+        impl #impl_generics ::pupactor::ShutdownReason for #struct_name #ty_generics #where_clause {
+            #[inline(always)]
+            fn reason(&self) -> ::std::borrow::Cow<'_, str> {
+                ::std::borrow::Cow::Borrowed(#reason)
+            }
+        }
+
+        #(#from_impls)*
+
+        #display
+    })
 }